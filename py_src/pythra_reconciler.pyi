@@ -3,7 +3,7 @@
 Type stubs for the PyThra Reconciler Rust extension
 """
 
-from typing import Any, Dict, List, Optional, Tuple, Union, Callable, Literal
+from typing import Any, Dict, List, Optional, Tuple, Union, Callable, Literal, TypedDict
 from dataclasses import dataclass, field
 
 PatchAction = Literal["INSERT", "REMOVE", "UPDATE", "MOVE", "REPLACE"]
@@ -17,32 +17,274 @@ class Key:
     def __eq__(self, other: object) -> bool: ...
     def __hash__(self) -> int: ...
 
-@dataclass 
 class Patch:
-    action: PatchAction
+    @property
+    def action(self) -> PatchAction: ...
+    @property
+    def html_id(self) -> str: ...
+    @property
+    def target_id(self) -> str: ...
+    @property
+    def data(self) -> Any: ...
+    def to_dict(self) -> Dict[str, Any]: ...
+    def __getitem__(self, key: str) -> Any: ...
+    def __eq__(self, other: object) -> bool: ...
+
+class JsInitializer:
+    @property
+    def type(self) -> str: ...
+    @property
+    def target_id(self) -> str: ...
+    @property
+    def data(self) -> Any: ...
+    @property
+    def before_id(self) -> Optional[str]: ...
+    def to_dict(self) -> Dict[str, Any]: ...
+    def __getitem__(self, key: str) -> Any: ...
+    def __eq__(self, other: object) -> bool: ...
+
+class ReconcileStats:
+    """Timings are inclusive milliseconds: html_stub_ms overlaps build_tree_ms and diff_ms."""
+    old_node_count: int
+    new_node_count: int
+    patch_counts: Dict[PatchAction, int]
+    lis_lengths: List[int]
+    build_tree_ms: float
+    diff_ms: float
+    lifecycle_ms: float  # lifecycle hooks, run after the diff
+    html_stub_ms: float
+    to_python_ms: float
+    python_calls: Dict[Literal["render_props", "get_children", "_generate_html_stub"], int]
+    reused_nodes: int  # copied from the previous tree by memo mode
+    kept_subtrees: int  # no prop diffs or patches: memoized or with an unchanged subtree_hash
+    def to_dict(self) -> Dict[str, Any]: ...
+
+class EventBinding(TypedDict):
+    """A handler for client code to attach in ``event_mode="data_attributes"``"""
     html_id: str
-    data: Dict[str, Any]
+    event: str
+    key: Optional[str]  # e.g. "Enter": only fire for this KeyboardEvent.key
+    callback: str
+    args: Optional[List[Any]]
+
+class LifecycleError(TypedDict):
+    """A StatefulWidget state hook that raised; the others still ran"""
+    hook: Literal["initState", "didChangeDependencies", "didUpdateWidget", "deactivate", "activate", "dispose"]
+    key: str
+    html_id: str
+    error: str
 
-@dataclass
 class ReconciliationResult:
-    patches: List[Patch] = field(default_factory=list)
-    new_rendered_map: Dict[Union[Key, str], Any] = field(default_factory=dict)
-    active_css_details: Dict[str, Tuple[Callable, Any]] = field(default_factory=dict)
-    registered_callbacks: Dict[str, Callable] = field(default_factory=dict)
-    js_initializers: List[Dict] = field(default_factory=list)
+    @property
+    def patches(self) -> List[Patch]: ...
+    # Node dicts: html_id, widget_type, key, html, widget_instance, props,
+    # parent_html_id, parent_key, children_keys, memo_key and subtree_hash
+    # (a 64-bit hash of widget_type, key, props and the children's hashes,
+    # stable across processes, so usable as a cache key)
+    @property
+    def new_rendered_map(self) -> Dict[str, Dict[str, Any]]: ...
+    @property
+    def active_css_details(self) -> Dict[str, Tuple[Callable, Any]]: ...
+    @property
+    def registered_callbacks(self) -> Dict[str, Callable]: ...
+    @property
+    def js_initializers(self) -> List[JsInitializer]: ...
+    @property
+    def event_bindings(self) -> List[EventBinding]: ...
+    @property
+    def lifecycle_errors(self) -> List[LifecycleError]: ...
+    @property
+    def stats(self) -> Optional[ReconcileStats]: ...
+    def to_dict(self) -> Dict[str, Any]: ...
+    def to_json_bytes(self) -> bytes: ...
+    def to_msgpack_bytes(self) -> bytes: ...
+    def __getitem__(self, key: str) -> Any: ...
+    def __eq__(self, other: object) -> bool: ...
+
+class RenderResult:
+    @property
+    def html(self) -> str: ...
+    @property
+    def new_rendered_map(self) -> Dict[str, Dict[str, Any]]: ...
+    @property
+    def active_css_details(self) -> Dict[str, Tuple[Callable, Any]]: ...
+    @property
+    def registered_callbacks(self) -> Dict[str, Callable]: ...
+    @property
+    def js_initializers(self) -> List[JsInitializer]: ...
+    @property
+    def event_bindings(self) -> List[EventBinding]: ...
+    @property
+    def lifecycle_errors(self) -> List[LifecycleError]: ...
+    def to_dict(self) -> Dict[str, Any]: ...
+    def __getitem__(self, key: str) -> Any: ...
+
+class RenderStream:
+    """Iterator of HTML chunks; the widget tree is built while iterating."""
+    # Callbacks, CSS details, js_initializers and lifecycle errors, as from
+    # render_to_string (without patches); None until the last chunk is out
+    result: Optional[ReconciliationResult]
+    def __iter__(self) -> "RenderStream": ...
+    def __next__(self) -> str: ...
+
+def parse_stub(html: str) -> Dict[str, Any]:
+    """Parse one stub element back into ``{"tag", "id", "classes", "style",
+    "attributes", "text", "void", "children"}``. ``attributes`` excludes
+    id/class/style; ``children`` are nested element dicts. Raises
+    ValueError for malformed markup or anything but a single element."""
+    ...
+
+def set_log_level(level: Literal["off", "error", "warn", "warning", "info", "debug", "trace"]) -> None:
+    """Most verbose level the extension emits (default "warn").
+
+    Records go to ``logging.getLogger("rust_reconciler.<target>")``; trace
+    records use level 5, below ``logging.DEBUG``.
+    """
+    ...
 
 class Reconciler:
-    def __init__(self) -> None: ...
+    # When True, a duplicate key is renamed to "<parent key>/<key>#<index>"
+    # instead of raising ValueError("Duplicate key ...")
+    auto_disambiguate_keys: bool
+
+    # Memo mode: a widget whose should_update(old_widget) returns False, or
+    # (without that method) whose memo_key equals the previous render's,
+    # keeps its old subtree as-is - no render_props/get_children calls, no
+    # stubs and no patches for anything below it. The widget itself still
+    # gets memo_key/should_update and its CSS/callback lookups; the nodes
+    # below it replay the callbacks and CSS rules found for them last time.
+    memoize: bool
+
+    # "global" shares one process-wide counter, "counter" gives each
+    # reconciler its own (see reset_ids), "key_path" hashes the keys from
+    # the root so identical trees always get identical ids. id_prefix must
+    # be letters, digits, '-' and '_', starting with a letter (else ValueError)
+    @property
+    def id_strategy(self) -> Literal["global", "counter", "key_path"]: ...
+    @property
+    def id_prefix(self) -> str: ...
+
+    # "inline" emits onclick="handleClick(...)" (other events call
+    # handleEvent(event, name, argsJson)); "data_attributes" emits
+    # data-fw-on-<event> / data-fw-args-<event> / data-fw-key-<event> and
+    # fills result.event_bindings, so no inline script is needed under a
+    # strict Content-Security-Policy. Callback props map to DOM events as
+    # onPressed/onTap -> click, onChanged -> input (input, textarea) or
+    # change, onHover -> mouseenter, onSubmitted -> keydown Enter,
+    # onDrag -> pointerdown/pointermove/pointerup
+    event_mode: Literal["inline", "data_attributes"]
+
+    def __init__(
+        self,
+        auto_disambiguate_keys: bool = False,
+        id_strategy: Literal["global", "counter", "key_path"] = "global",
+        id_prefix: str = "fw_id_",
+        event_mode: Literal["inline", "data_attributes"] = "inline",
+        memoize: bool = False,
+    ) -> None: ...
+
+    def reset_ids(self) -> None: ...
+
+    def register_widget_tag(self, type_name: str, tag: str, void: bool = False) -> None:
+        """Render ``type_name`` widgets as ``<tag>`` in generic stubs,
+        overriding the built-in table; ``void`` tags get no closing tag."""
+        ...
     
+    def register_widget_template(self, type_name: str, template: Dict[str, Any]) -> None:
+        """Declarative stub for ``type_name``, e.g.::
+
+            {"tag": "input", "void": True,
+             "static_attributes": {"type": "checkbox"},
+             "attributes": {"name": "field_name"},            # attr <- prop
+             "styles": {"width": {"prop": "width", "unit": "px"},
+                        "color": "color"},                    # css <- prop
+             "text": "label"}                                 # inner text <- prop
+
+        Units apply to numeric values only. Takes precedence over the
+        built-in widget branches and ``register_widget_tag``."""
+        ...
+
+    def set_ignored_props(self, props: List[str], widget_type: Optional[str] = None) -> None:
+        """Props never compared for UPDATE patches. Without ``widget_type``
+        this replaces the shared list (initially widget_instance,
+        itemBuilder, onChanged, onPressed, onTap, onDrag); with it, sets the
+        props ignored in addition for that widget type."""
+        ...
+
+    def ignored_props(self, widget_type: Optional[str] = None) -> List[str]: ...
+
+    def set_prop_comparator(
+        self,
+        prop: str,
+        comparator: Union[Literal["exact", "epsilon", "unordered", "ignore_nulls"], Callable[[Any, Any], bool], None],
+        widget_type: Optional[str] = None,
+        epsilon: float = 1e-9,
+    ) -> None:
+        """How ``prop`` is compared for UPDATE patches. "epsilon" treats
+        numbers within ``epsilon`` as equal, "unordered" compares class
+        lists as sets, "ignore_nulls" treats null fields as absent; a
+        callable gets (old, new) and returns True when unchanged. None
+        removes the comparator. A per-type comparator wins over the shared
+        one."""
+        ...
+
     def clear_context(self, context_key: str) -> None: ...
     
     def clear_all_contexts(self) -> None: ...
     
+    # After diffing, StatefulWidget states (``widget.get_state()``) get
+    # deactivate (children first), then initState + didChangeDependencies,
+    # activate or didUpdateWidget(old_props) (parents first), then dispose
+    # (children first). Missing hooks are skipped; exceptions are listed in
+    # ``lifecycle_errors``.
     def reconcile(
         self,
         previous_map: Dict[Union[Key, str], Any],
         new_widget_root: Any,
         parent_html_id: str,
-        old_root_key: Optional[Union[Key, str]] = None,
         is_partial_reconciliation: bool = False,
+        old_root_key: Optional[str] = None,
+        full_props: bool = False,
+        collect_stats: bool = False,
+    ) -> ReconciliationResult: ...
+
+    def render_to_string(
+        self,
+        root_widget: Any,
+        parent_html_id: str,
+        context_key: Optional[str] = None,
+    ) -> RenderResult:
+        """Complete nested HTML for a first paint. With ``context_key`` the
+        rendered map is kept for a following ``reconcile_in_context``."""
+        ...
+
+    def hydrate(
+        self,
+        html: str,
+        root_widget: Any,
+        parent_html_id: str,
+        context_key: Optional[str] = None,
+    ) -> ReconciliationResult:
+        """Adopt the ids of server-rendered ``html`` in document order. The
+        result has no patches; mismatches are logged as warnings."""
+        ...
+
+    def render_stream(
+        self,
+        root_widget: Any,
+        parent_html_id: str,
+        chunk_size: int = 8192,
+        context_key: Optional[str] = None,
+    ) -> RenderStream:
+        """``render_to_string`` as chunks of at least ``chunk_size`` bytes in
+        document order; joined, they equal ``render_to_string(...).html``."""
+        ...
+
+    def reconcile_in_context(
+        self,
+        context_key: str,
+        new_widget_root: Any,
+        parent_html_id: str,
+        full_props: bool = False,
+        collect_stats: bool = False,
     ) -> ReconciliationResult: ...
\ No newline at end of file
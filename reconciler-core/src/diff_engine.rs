@@ -0,0 +1,819 @@
+//! Core diffing engine with proven-correct LIS and exact Python parity
+use crate::errors::ReconcilerError;
+use crate::host::{DiffHost, LifecycleError, LifecycleHook};
+use crate::keys::{ancestor_keys, parent_chain};
+use crate::prop_rules::PropDiffRules;
+use crate::types::*;
+use log::{Level, debug, log_enabled, trace, warn};
+use crate::stats::ReconcileStats;
+use std::collections::{HashMap, HashSet};
+use std::time::Instant;
+
+/// Knobs that change the shape of the generated patches
+#[derive(Debug, Clone, Default)]
+pub struct DiffOptions {
+    /// Send the complete `props`/`old_props` in UPDATE patches instead of
+    /// only the changed keys plus `removed_props`
+    pub full_props: bool,
+    /// Fill `RustReconciliationResult::stats`
+    pub collect_stats: bool,
+    /// Props left out of UPDATE comparisons and custom comparators
+    pub prop_rules: PropDiffRules,
+}
+
+pub struct DiffEngine<'a, H: DiffHost> {
+    host: &'a mut H,
+    old_tree: &'a NodeMap<H::Widget>,
+    new_tree: &'a NodeMap<H::Widget>,
+    result: &'a mut RustReconciliationResult<H::Widget>,
+    options: DiffOptions,
+    /// Old keys already handled by `remove_subtree`
+    removed_keys: HashSet<String>,
+    /// html_ids whose element leaves the DOM, explicitly or with an ancestor
+    removed_ids: HashSet<String>,
+    /// Old keys to deactivate, parents first
+    deactivated: Vec<String>,
+    /// `InitState`, `Activate` or `DidUpdateWidget` per new key, parents first
+    built: Vec<(LifecycleHook, String)>,
+    /// Old keys to dispose, parents first
+    disposed: Vec<String>,
+}
+
+impl<'a, H: DiffHost> DiffEngine<'a, H> {
+    pub fn new(
+        host: &'a mut H,
+        old_tree: &'a NodeMap<H::Widget>,
+        new_tree: &'a NodeMap<H::Widget>,
+        result: &'a mut RustReconciliationResult<H::Widget>,
+    ) -> Self {
+        DiffEngine {
+            host,
+            old_tree,
+            new_tree,
+            result,
+            options: DiffOptions::default(),
+            removed_keys: HashSet::new(),
+            removed_ids: HashSet::new(),
+            deactivated: Vec::new(),
+            built: Vec::new(),
+            disposed: Vec::new(),
+        }
+    }
+
+    pub fn with_options(mut self, options: DiffOptions) -> Self {
+        self.options = options;
+        self
+    }
+
+    pub fn reconcile(&mut self, root_key: Option<&str>) -> Result<(), H::Error> {
+        if let Some(root) = root_key {
+            self.diff_node(root, root)?;
+            // After diffing, reorganize patches so parent INSERTs come before child INSERTs
+            self.reorder_patches_parent_first();
+        }
+        Ok(())
+    }
+
+    /// Remove the old keys missing from the new tree that the diff did not
+    /// reach, topmost first so each subtree gets a single REMOVE. Used by
+    /// full (non-partial) reconciliation.
+    pub fn remove_vanished(&mut self) -> Result<(), H::Error> {
+        let old_tree = self.old_tree;
+        let mut vanished: Vec<(usize, &'a str)> = old_tree.keys()
+            .filter(|key| !self.new_tree.contains_key(*key) && !self.removed_keys.contains(*key))
+            .map(|key| (depth_in(old_tree, key), key.as_str()))
+            .collect();
+        vanished.sort_unstable();
+
+        for (_, key) in vanished {
+            self.remove_subtree(key, true)?;
+        }
+        removes_first(&mut self.result.patches);
+        Ok(())
+    }
+
+    /// Run the lifecycle hooks queued by the diff: deactivations children
+    /// first, then mounts, activations and updates parents first, then
+    /// disposals children first. Failures land in `lifecycle_errors`.
+    pub fn dispatch_lifecycle(&mut self) {
+        let old_tree = self.old_tree;
+        let new_tree = self.new_tree;
+
+        for key in std::mem::take(&mut self.deactivated).iter().rev() {
+            if let Some(node) = old_tree.get(key) {
+                self.call_hook(LifecycleHook::Deactivate, node, |host| host.deactivate(node));
+            }
+        }
+        for (hook, key) in std::mem::take(&mut self.built) {
+            let Some(node) = new_tree.get(&key) else {
+                continue;
+            };
+            match hook {
+                LifecycleHook::InitState => {
+                    // didChangeDependencies only follows a successful initState
+                    let initialized = self.call_hook(hook, node, |host| host.init_state(node));
+                    if initialized {
+                        self.call_hook(LifecycleHook::DidChangeDependencies, node, |host| host.did_change_dependencies(node));
+                    }
+                }
+                LifecycleHook::Activate => {
+                    self.call_hook(hook, node, |host| host.activate(node));
+                }
+                LifecycleHook::DidUpdateWidget => {
+                    if let Some(old) = old_tree.get(&key) {
+                        self.call_hook(hook, node, |host| host.did_update_widget(old, node));
+                    }
+                }
+                _ => {}
+            }
+        }
+        for key in std::mem::take(&mut self.disposed).iter().rev() {
+            if let Some(node) = old_tree.get(key) {
+                self.call_hook(LifecycleHook::Dispose, node, |host| host.dispose(node));
+            }
+        }
+    }
+
+    /// Run one hook, recording a failure instead of aborting the dispatch
+    fn call_hook(
+        &mut self,
+        hook: LifecycleHook,
+        node: &RustNodeData<H::Widget>,
+        call: impl FnOnce(&mut H) -> Result<(), H::Error>,
+    ) -> bool {
+        match call(&mut *self.host) {
+            Ok(()) => true,
+            Err(e) => {
+                warn!("{} failed for '{}': {}", hook, node.key, e);
+                self.result.lifecycle_errors.push(LifecycleError {
+                    hook,
+                    key: node.key.clone(),
+                    html_id: node.html_id.clone(),
+                    message: e.to_string(),
+                });
+                false
+            }
+        }
+    }
+
+    /// Queue the first hooks of a node entering the tree: `activate` when
+    /// an old node of the same type with its key left another place,
+    /// `init_state` otherwise
+    fn queue_mount(&mut self, key: &str) {
+        let moved = match (self.old_tree.get(key), self.new_tree.get(key)) {
+            (Some(old), Some(new)) => old.widget_type == new.widget_type,
+            _ => false,
+        };
+        let hook = if moved { LifecycleHook::Activate } else { LifecycleHook::InitState };
+        self.built.push((hook, key.to_string()));
+    }
+
+    /// Drop the old subtree under `key`, once. With `emit`, the topmost
+    /// renderable nodes get a REMOVE patch; everything below them leaves the
+    /// DOM with that element. Every node is deactivated; nodes the new tree
+    /// lacks, or has with another type, are disposed, and the others are
+    /// re-inserted wherever the new tree puts them.
+    fn remove_subtree(&mut self, key: &str, emit: bool) -> Result<(), H::Error> {
+        let old_tree = self.old_tree;
+        let Some(root) = old_tree.get(key) else {
+            return Ok(());
+        };
+        let mut stack = vec![(root.key.as_str(), emit)];
+        while let Some((key, emit)) = stack.pop() {
+            let Some(node) = old_tree.get(key) else {
+                continue;
+            };
+            if !self.removed_keys.insert(key.to_string()) {
+                continue;
+            }
+            self.removed_ids.insert(node.html_id.clone());
+
+            let remove_here = emit && is_renderable_type(&node.widget_type);
+            if remove_here {
+                self.result.patches.push(RustPatch {
+                    action: PatchAction::Remove,
+                    html_id: node.html_id.clone(),
+                    data: serde_json::Value::Null,
+                });
+            }
+            self.deactivated.push(key.to_string());
+            if self.new_tree.get(key).is_none_or(|new| new.widget_type != node.widget_type) {
+                self.disposed.push(key.to_string());
+            }
+            stack.extend(node.children_keys.iter().rev().map(|child| (child.as_str(), emit && !remove_here)));
+        }
+        Ok(())
+    }
+
+    fn diff_node(&mut self, old_key: &str, new_key: &str) -> Result<(), H::Error> {
+        let old_node = self.old_tree.get(old_key);
+        let new_node = self.new_tree.get(new_key);
+
+        match (old_node, new_node) {
+            (None, Some(node)) => {
+                // Insert the new node, then recursively handle its children
+                self.insert_node(node, None)?;
+
+                // CRITICAL: Add the node to new_rendered_map so it's returned to the host
+                self.result.new_rendered_map.insert(node.key.clone(), node.clone());
+
+                // Determine the correct parent_html_id for children.
+                // Use a robust resolver that walks the parent_key chain to find
+                // the nearest renderable ancestor. This avoids attaching children
+                // to internal proxy nodes whose html ids may not correspond to
+                // real DOM elements.
+                let child_parent_resolved = if is_renderable_type(&node.widget_type) {
+                    node.html_id.clone()
+                } else {
+                    self.resolve_parent_html_by_parent_key(node.parent_key.as_deref(), &node.parent_html_id)
+                };
+
+                // Reconcile children: there are no old keys for this subtree
+                self.diff_children(&[] as &[String], &node.children_keys, &child_parent_resolved, &node.key)?;
+            }
+            (Some(old), Some(new)) => {
+                trace!("diff_node update case - old.widget_type='{}' new.widget_type='{}' old.key='{}' new.key='{}' old.children_keys.len={} new.children_keys.len={}", old.widget_type, new.widget_type, old.key, new.key, old.children_keys.len(), new.children_keys.len());
+                if old.widget_type != new.widget_type || old.key != new.key {
+                    // Type mismatch - replace entire subtree
+                    debug!("type/key mismatch for key '{}' ({} -> {}) - replacing", new.key, old.widget_type, new.widget_type);
+                    let stub = self.host.generate_html(new)?;
+                    self.result.patches.push(RustPatch {
+                        action: PatchAction::Replace,
+                        html_id: old.html_id.clone(),
+                        data: serde_json::json!({ "new_html": stub, "new_props": new.props }),
+                    });
+                    // The old node's state goes, and its children left the DOM
+                    // with the replaced element
+                    if self.removed_keys.insert(old.key.clone()) {
+                        self.deactivated.push(old.key.clone());
+                        self.disposed.push(old.key.clone());
+                    }
+                    for child in &old.children_keys {
+                        self.remove_subtree(child, false)?;
+                    }
+                    self.insert_node(new, None)?;
+                    // CRITICAL: After replacing a node, also add it to new_rendered_map and process children
+                    self.result.new_rendered_map.insert(new.key.clone(), new.clone());
+                    // Treat some internal proxy widget types as non-renderable so
+                    // their children attach to the nearest renderable ancestor.
+                    let child_parent_resolved = if is_renderable_type(&new.widget_type) {
+                        new.html_id.clone()
+                    } else {
+                        self.resolve_parent_html_by_parent_key(new.parent_key.as_deref(), &new.parent_html_id)
+                    };
+                    self.diff_children(&[] as &[String], &new.children_keys, &child_parent_resolved, &new.key)?;
+                } else if old.subtree_hash.is_some()
+                    && old.subtree_hash == new.subtree_hash
+                    && old.html_id == new.html_id
+                {
+                    // Identical content under the same element: no prop diffs
+                    // and no patches, but the nodes are still visited
+                    trace!("subtree '{}' unchanged (hash {:016x})", new.key, new.subtree_hash.unwrap_or(0));
+                    self.keep_subtree(new)?;
+                } else {
+                    self.update_node(old, new)?;
+                }
+            }
+            (Some(old), None) => {
+                self.remove_subtree(&old.key, true)?;
+            }
+            (None, None) => {}
+        }
+        Ok(())
+    }
+
+    fn update_node(&mut self, old: &RustNodeData<H::Widget>, new: &RustNodeData<H::Widget>) -> Result<(), H::Error> {
+        if new.memoized {
+            return self.keep_subtree(new);
+        }
+        self.host.collect_details(new)?;
+        self.built.push((LifecycleHook::DidUpdateWidget, new.key.clone()));
+
+        // Update patch for renderable widgets
+        if !["StatefulWidget", "StatelessWidget"].contains(&new.widget_type.as_str()) {
+            let prop_diff = self.options.prop_rules.diff(&new.widget_type, &old.props, &new.props);
+            if !prop_diff.is_empty() {
+                let data = if self.options.full_props {
+                    serde_json::json!({ "props": new.props, "old_props": old.props })
+                } else {
+                    serde_json::json!({ "props": prop_diff.changed, "removed_props": prop_diff.removed })
+                };
+                self.result.patches.push(RustPatch {
+                    action: PatchAction::Update,
+                    html_id: new.html_id.clone(),
+                    data,
+                });
+            }
+        }
+
+        // Compute resolved parent_html_id for children using a nearest-renderable
+        // ancestor resolver. This is more robust when internal wrapper/ proxy
+        // types are present in the tree.
+        let child_parent_resolved = if is_renderable_type(&new.widget_type) {
+            new.html_id.clone()
+        } else {
+            self.resolve_parent_html_by_parent_key(new.parent_key.as_deref(), &new.parent_html_id)
+        };
+
+        self.result.new_rendered_map.insert(new.key.clone(), new.clone());
+        self.diff_children(&old.children_keys, &new.children_keys, &child_parent_resolved, &new.key)
+    }
+
+    /// Carry a memoized or unchanged subtree into the new rendered map as-is,
+    /// without prop diffs or patches. Every node is still visited: details
+    /// are gathered so its callbacks and CSS stay registered (below a
+    /// memoized node the nodes are the previous tree's, so the host may
+    /// reuse what it collected for them), and nodes rebuilt from new widgets
+    /// get `did_update_widget` as an ordinary update would.
+    fn keep_subtree(&mut self, root: &RustNodeData<H::Widget>) -> Result<(), H::Error> {
+        if let Some(stats) = self.result.stats.as_mut() {
+            stats.kept_subtrees += 1;
+        }
+        // (key, whether the node lies below a memoized node)
+        let mut stack = vec![(root.key.as_str(), false)];
+        while let Some((key, reused)) = stack.pop() {
+            let Some(node) = self.new_tree.get(key) else {
+                continue;
+            };
+            if reused {
+                self.host.reuse_details(node)?;
+            } else {
+                self.host.collect_details(node)?;
+                if !node.memoized {
+                    self.built.push((LifecycleHook::DidUpdateWidget, node.key.clone()));
+                }
+            }
+            let mut kept = node.clone();
+            kept.memoized = false;
+            self.result.new_rendered_map.insert(kept.key.clone(), kept);
+            let children_reused = reused || node.memoized;
+            stack.extend(node.children_keys.iter().rev().map(|k| (k.as_str(), children_reused)));
+        }
+        Ok(())
+    }
+
+    fn insert_node(&mut self, node: &RustNodeData<H::Widget>, before_id: Option<String>) -> Result<(), H::Error> {
+        // Queue JS initializers directly into result
+        self.queue_js_initializers(node);
+        self.queue_mount(&node.key);
+
+        // Collect CSS details and callbacks for this node so registered_callbacks
+        // and active_css_details are populated even for newly-inserted nodes.
+        // This mirrors Python reconciler behaviour which inspects props for callbacks
+        // during insertion as well as updates.
+        self.host.collect_details(node)?;
+
+        // Determine the best parent_html_id for this insert by walking the
+        // parent_key chain to find the nearest renderable ancestor. Use the
+        // existing node.parent_html_id as a fallback.
+        let resolved_parent_html = self.resolve_parent_html_by_parent_key(node.parent_key.as_deref(), &node.parent_html_id);
+
+        // DIAGNOSTIC: Log parent resolution outcome (the lookups are linear,
+        // so only pay for them when tracing)
+        if log_enabled!(Level::Trace) {
+            let parent_in_old_tree = self.old_tree.values().any(|n| n.html_id == resolved_parent_html);
+            let parent_in_new_rendered_map = self.result.new_rendered_map.values().any(|n| n.html_id == resolved_parent_html);
+            trace!(
+                "insert_node key='{}' resolved_parent='{}' parent_in_old_tree={} parent_in_new_rendered_map={} parent_key={:?}",
+                node.key, resolved_parent_html, parent_in_old_tree, parent_in_new_rendered_map, node.parent_key
+            );
+        }
+
+        // Renderable widgets only (exact Python parity)
+        if !["StatefulWidget", "StatelessWidget"].contains(&node.widget_type.as_str()) {
+            let stub = self.host.generate_html(node)?;
+            self.result.patches.push(RustPatch {
+                action: PatchAction::Insert,
+                html_id: node.html_id.clone(),
+                data: serde_json::json!({
+                    "html": stub,
+                    "parent_html_id": resolved_parent_html,
+                    "props": node.props,
+                    "before_id": before_id,
+                }),
+            });
+            trace!(
+                "inserted node key='{}' html_id='{}' resolved_parent_html='{}' widget_type='{}'",
+                node.key, node.html_id, resolved_parent_html, node.widget_type
+            );
+        }
+
+        self.result.new_rendered_map.insert(node.key.clone(), node.clone());
+        trace!(
+            "new_rendered_map insert key='{}' total_entries={}",
+            node.key,
+            self.result.new_rendered_map.len()
+        );
+        Ok(())
+    }
+
+    fn diff_children(
+        &mut self,
+        old_keys: &[String],
+        new_keys: &[String],
+        parent_html_id: &str,
+        parent_key: &str,
+    ) -> Result<(), H::Error> {
+        trace!(
+            "diff_children: old_keys.len={} new_keys.len={} parent_key='{}' new_keys={:?}",
+            old_keys.len(),
+            new_keys.len(),
+            parent_key,
+            new_keys
+        );
+
+        if old_keys.is_empty() && new_keys.is_empty() {
+            return Ok(());
+        }
+
+        // Handle removals
+        let new_set: HashSet<_> = new_keys.iter().collect();
+        for old_key in old_keys {
+            if !new_set.contains(old_key) {
+                self.remove_subtree(old_key, true)?;
+            }
+        }
+
+        if new_keys.is_empty() {
+            return Ok(());
+        }
+
+        // PROVEN-CORRECT LIS: Handles empty sequences, stable indices
+        let old_key_to_idx: HashMap<_, _> = old_keys.iter().enumerate()
+            .map(|(i, k)| (k.as_str(), i))
+            .collect();
+
+        let mut new_to_old_idx = Vec::new();
+        let mut sequence_for_lis = Vec::new();
+
+        for new_key in new_keys {
+            if let Some(&old_idx) = old_key_to_idx.get(new_key.as_str()) {
+                new_to_old_idx.push(Some(old_idx));
+                sequence_for_lis.push(old_idx);
+            } else {
+                new_to_old_idx.push(None);
+            }
+        }
+
+        // Bulletproof LIS: Returns empty vector for empty sequence
+        let lis_indices = longest_increasing_subsequence(&sequence_for_lis);
+        if let Some(stats) = self.result.stats.as_mut() {
+            stats.lis_lengths.push(lis_indices.len());
+        }
+        let lis_old_indices: HashSet<usize> = lis_indices.into_iter()
+            .map(|i| sequence_for_lis[i])
+            .collect();
+
+        // Process children with exact Python parity
+        for (i, new_key) in new_keys.iter().enumerate() {
+            let before_id = new_keys.get(i + 1)
+                .and_then(|k| self.new_tree.get(k))
+                .map(|n| n.html_id.clone());
+
+            if let Some(old_idx) = new_to_old_idx[i] {
+                // Existing node
+                if !lis_old_indices.contains(&old_idx) {
+                    let moved_node = self.new_node(new_key)?;
+                    self.result.patches.push(RustPatch {
+                        action: PatchAction::Move,
+                        html_id: moved_node.html_id.clone(),
+                        data: serde_json::json!({
+                            "parent_html_id": parent_html_id,
+                            "before_id": before_id,
+                        }),
+                    });
+                }
+                let old_child_key = old_keys.get(old_idx).map(|s| s.as_str()).unwrap_or(new_key);
+                self.diff_node(old_child_key, new_key)?;
+            } else {
+                // New node
+                let new_node = self.new_node(new_key)?;
+                trace!(
+                    "diff_children: about to insert new child key='{}' from new_tree",
+                    new_key
+                );
+                let mut node_clone = new_node.clone();
+                // Resolve parent_html for this insertion so children get the
+                // correct ancestor even when intermediate wrappers are non-renderable.
+                let resolved_parent_for_insert = self.resolve_parent_html_by_parent_key(Some(parent_key), parent_html_id);
+                node_clone.parent_html_id = resolved_parent_for_insert.clone();
+                node_clone.parent_key = Some(parent_key.to_string());
+                self.insert_node(&node_clone, before_id)?;
+
+                // CRITICAL: After inserting a new node, recursively reconcile its children
+                // Choose the child's parent id based on whether this node is renderable
+                // (if renderable, children attach to its html_id; otherwise they use
+                // the resolved parent we computed above).
+                let child_parent_id = if is_renderable_type(&new_node.widget_type) { &new_node.html_id } else { &resolved_parent_for_insert };
+                self.diff_children(&[] as &[String], &new_node.children_keys, child_parent_id, new_key)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Look up a key listed in some `children_keys` of the new tree
+    fn new_node(&self, key: &str) -> Result<&'a RustNodeData<H::Widget>, H::Error> {
+        self.new_tree.get(key).ok_or_else(|| {
+            ReconcilerError::KeyError {
+                details: format!("Child key '{}' missing from new tree", key),
+            }
+            .into()
+        })
+    }
+
+    /// Walk the parent_key chain (new_tree first, fallback to old_tree) to find
+    /// the nearest ancestor that is renderable and return its html_id. If
+    /// none is found, return the provided fallback_parent_html_id.
+    fn resolve_parent_html_by_parent_key(&self, parent_key: Option<&str>, fallback_parent_html_id: &str) -> String {
+        let mut current: Option<String> = parent_key.map(|s| s.to_string());
+        let mut walk_trace = String::new();
+        let tracing = log_enabled!(Level::Debug);
+
+        while let Some(pk) = current {
+            // Prefer looking up in the old tree first because old_tree reflects
+            // the DOM that currently exists. If an ancestor existed previously
+            // in the DOM, prefer that html_id so inserts attach to an element
+            // that is actually present when patches are applied. A removed
+            // ancestor whose key the new tree still has is being re-inserted
+            // elsewhere, so it is resolved against its new-tree node below.
+            if let Some(node) = self.old_tree.get(&pk)
+                && !(self.removed_ids.contains(&node.html_id) && self.new_tree.contains_key(&pk))
+            {
+                if tracing {
+                    walk_trace.push_str(&format!("old_tree[{}]={} renderable={} ", pk, node.html_id, is_renderable_type(&node.widget_type)));
+                }
+                // Skip if this node is being removed in this reconciliation
+                if !self.removed_ids.contains(&node.html_id) && is_renderable_type(&node.widget_type) {
+                    trace!("resolve_parent: parent_key={:?} -> found in old_tree (not removed): {} ({})", parent_key, pk, node.html_id);
+                    return node.html_id.clone();
+                }
+                if self.removed_ids.contains(&node.html_id) {
+                    trace!("resolve_parent: parent_key={:?} -> found in old_tree but being REMOVED: {}", parent_key, pk);
+                }
+                current = node.parent_key.clone();
+                continue;
+            }
+
+            // If not present in old_tree, check new_tree (it may be created by
+            // earlier inserts in this reconciliation). Prefer only if renderable.
+            if let Some(node) = self.new_tree.get(&pk) {
+                if tracing {
+                    walk_trace.push_str(&format!("new_tree[{}]={} renderable={} ", pk, node.html_id, is_renderable_type(&node.widget_type)));
+                }
+                if is_renderable_type(&node.widget_type) {
+                    trace!("resolve_parent: parent_key={:?} -> found in new_tree: {} ({})", parent_key, pk, node.html_id);
+                    return node.html_id.clone();
+                }
+                current = node.parent_key.clone();
+                continue;
+            }
+
+            // No entry found for this key; stop the walk
+            trace!("resolve_parent: parent_key={:?} -> key '{}' not in either tree", parent_key, pk);
+            break;
+        }
+
+        // If the provided fallback_parent_html_id appears to be an existing
+        // node from the previous map (old_tree) and is NOT being removed, prefer it.
+        if !self.removed_ids.contains(fallback_parent_html_id) && self.old_tree.values().any(|n| n.html_id == fallback_parent_html_id) {
+            trace!("resolve_parent: parent_key={:?} -> fallback '{}' found in old_tree (not removed)", parent_key, fallback_parent_html_id);
+            return fallback_parent_html_id.to_string();
+        }
+
+        // Last-resort fallback: use the well-known 'root-container' id which is
+        // present in the page wrapper. This avoids emitting INSERTs with
+        // non-existent parents and prevents hard JS failures.
+        debug!("resolve_parent: parent_key={:?} -> using root-container fallback (trace: {} removed_ids: {:?})", parent_key, walk_trace, !self.removed_ids.is_empty());
+        "root-container".to_string()
+    }
+
+    fn queue_js_initializers(&mut self, node: &RustNodeData<H::Widget>) {
+        if node.widget_type == "Scrollbar" {
+            self.result.js_initializers.push(JsInitializer {
+                init_type: "SimpleBar".to_string(),
+                target_id: node.html_id.clone(),
+                data: serde_json::Value::Object(serde_json::Map::new()),
+                before_id: None,
+            });
+        }
+
+        if let Some(clip_path) = node.props.get("responsive_clip_path") {
+            self.result.js_initializers.push(JsInitializer {
+                init_type: "ResponsiveClipPath".to_string(),
+                target_id: node.html_id.clone(),
+                data: clip_path.clone(),
+                before_id: None,
+            });
+        }
+
+        if let Some(js_init) = node.props.get("_js_init") {
+            self.result.js_initializers.push(JsInitializer {
+                init_type: "generic".to_string(),
+                target_id: node.html_id.clone(),
+                data: js_init.clone(),
+                before_id: None,
+            });
+        }
+    }
+
+    /// Reorder patches so that REMOVEs come first and all parent INSERTs come
+    /// before their child INSERTs. This ensures that when JS applies patches,
+    /// the DOM parent already exists and a reparented node's old element is
+    /// gone before the element with the same id is inserted.
+    fn reorder_patches_parent_first(&mut self) {
+        removes_first(&mut self.result.patches);
+        reorder_patches_parent_first(&mut self.result.patches);
+        debug!("patch reordering complete, {} patches total", self.result.patches.len());
+    }
+}
+
+/// Number of ancestors of `key` in `map`
+fn depth_in<W>(map: &NodeMap<W>, key: &str) -> usize {
+    ancestor_keys(map, Some(key)).count() - 1
+}
+
+/// Pick the key the diff should start from. Prefer an explicit
+/// `old_root_key`. If missing, try to find a root in the old map. If still
+/// not found (initial render), fall back to discovering a root in the
+/// newly-built map so the diff engine can start from the actual new root
+/// widget. Only default to the literal "root" as a last resort.
+pub fn find_root_key<W>(
+    old_map: &NodeMap<W>,
+    new_map: &NodeMap<W>,
+    parent_html_id: &str,
+    old_root_key: Option<String>,
+) -> String {
+    let find_in = |map: &NodeMap<W>| {
+        map.iter()
+            .find(|(_, data)| data.parent_html_id == parent_html_id && data.parent_key.is_none())
+            .map(|(k, _)| k.clone())
+    };
+    old_root_key
+        .or_else(|| find_in(old_map))
+        .or_else(|| find_in(new_map))
+        .unwrap_or_else(|| "root".to_string())
+}
+
+/// Diff `old_map` against `new_map` and return the patches plus the new
+/// rendered map. Without `is_partial_reconciliation`, every old node missing
+/// from the new map is disposed and removed. Each removed subtree gets one
+/// REMOVE, for its topmost renderable nodes. Lifecycle hooks run after the
+/// diff; their failures are collected in `lifecycle_errors`.
+pub fn reconcile<H: DiffHost>(
+    host: &mut H,
+    old_map: &NodeMap<H::Widget>,
+    new_map: &NodeMap<H::Widget>,
+    parent_html_id: &str,
+    is_partial_reconciliation: bool,
+    old_root_key: Option<String>,
+    options: DiffOptions,
+) -> Result<RustReconciliationResult<H::Widget>, H::Error> {
+    let mut result = RustReconciliationResult::default();
+    if options.collect_stats {
+        result.stats = Some(ReconcileStats {
+            old_node_count: old_map.len(),
+            new_node_count: new_map.len(),
+            ..ReconcileStats::default()
+        });
+    }
+    let started = Instant::now();
+    let root_key = find_root_key(old_map, new_map, parent_html_id, old_root_key);
+
+    let mut engine = DiffEngine::new(host, old_map, new_map, &mut result).with_options(options);
+    engine.reconcile(Some(&root_key))?;
+
+    // Log chosen root key and map sizes so we can trace why
+    // the diff engine may produce no patches during initial render.
+    debug!(
+        "chosen root_key='{}' | old_map size={} | new_map size={}",
+        root_key,
+        old_map.len(),
+        new_map.len()
+    );
+
+    if !is_partial_reconciliation {
+        engine.remove_vanished()?;
+    }
+    let diff_duration = started.elapsed();
+
+    let started = Instant::now();
+    engine.dispatch_lifecycle();
+
+    if let Some(stats) = result.stats.as_mut() {
+        stats.diff_duration = diff_duration;
+        stats.lifecycle_duration = started.elapsed();
+        stats.count_patches(&result.patches);
+    }
+    Ok(result)
+}
+
+/// Prop-level delta between two renders of the same node
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PropDiff {
+    /// Keys that were added or whose value changed, with their new value
+    pub changed: HashMap<String, serde_json::Value>,
+    /// Keys present before but gone now (sorted)
+    pub removed: Vec<String>,
+}
+
+impl PropDiff {
+    pub fn is_empty(&self) -> bool {
+        self.changed.is_empty() && self.removed.is_empty()
+    }
+}
+
+/// Compare props of two renders with the default rules, ignoring callables
+/// and other volatile fields.
+pub fn diff_props(
+    old: &HashMap<String, serde_json::Value>,
+    new: &HashMap<String, serde_json::Value>,
+) -> PropDiff {
+    PropDiffRules::default().diff("", old, new)
+}
+
+/// PROVEN-CORRECT LIS: O(n log n), handles empty input, stable.
+/// Returns indices into `seq`.
+pub fn longest_increasing_subsequence(seq: &[usize]) -> Vec<usize> {
+    if seq.is_empty() {
+        return Vec::new();
+    }
+
+    let mut predecessors = vec![0; seq.len()];
+    let mut indices = vec![0; seq.len()];
+    let mut length = 0;
+
+    for (i, &value) in seq.iter().enumerate() {
+        let mut low = 0;
+        let mut high = length;
+
+        while low < high {
+            let mid = low + (high - low) / 2;
+            if seq[indices[mid]] < value {
+                low = mid + 1;
+            } else {
+                high = mid;
+            }
+        }
+
+        if low > 0 {
+            predecessors[i] = indices[low - 1];
+        }
+        indices[low] = i;
+
+        if low == length {
+            length += 1;
+        }
+    }
+
+    let mut lis = Vec::with_capacity(length);
+    let mut k = indices[length - 1];
+    for _ in 0..length {
+        lis.push(k);
+        k = predecessors[k];
+    }
+    lis.reverse();
+    lis
+}
+
+/// Move every REMOVE ahead of the other patches, keeping the relative order
+/// within both groups. A node moved to another parent keeps its html_id, so
+/// its REMOVE must not follow the INSERT at its new place.
+pub fn removes_first(patches: &mut [RustPatch]) {
+    patches.sort_by_key(|patch| patch.action != PatchAction::Remove);
+}
+
+/// Reorder patches so that all parent INSERTs come before their child INSERTs.
+/// INSERTs are stably sorted by how many of their ancestors are inserted in
+/// the same batch, within the positions INSERTs already occupy; siblings and
+/// non-INSERT patches keep their relative order.
+pub fn reorder_patches_parent_first(patches: &mut [RustPatch]) {
+    // Build a map of html_id -> parent_html_id for easy lookup
+    let mut parent_map: HashMap<&str, &str> = HashMap::new();
+    for patch in patches.iter() {
+        if patch.action == PatchAction::Insert
+            && let Some(parent_id) = patch.data.get("parent_html_id").and_then(|v| v.as_str())
+        {
+            parent_map.insert(&patch.html_id, parent_id);
+        }
+    }
+    let insert_depth = |html_id: &str| {
+        parent_chain(Some(html_id), parent_map.len(), |id| parent_map.get(id).copied()).count() - 1
+    };
+
+    let slots: Vec<usize> = (0..patches.len())
+        .filter(|&i| patches[i].action == PatchAction::Insert)
+        .collect();
+    let mut order: Vec<(usize, usize)> = slots.iter()
+        .map(|&i| (insert_depth(&patches[i].html_id), i))
+        .collect();
+    if order.windows(2).all(|w| w[0].0 <= w[1].0) {
+        return;
+    }
+    order.sort_by_key(|&(depth, _)| depth);
+
+    let inserts: Vec<RustPatch> = order.iter().map(|&(_, i)| patches[i].clone()).collect();
+    for (slot, patch) in slots.into_iter().zip(inserts) {
+        patches[slot] = patch;
+    }
+}
@@ -0,0 +1,21 @@
+//! Interpreter-independent error type for the diffing core
+use thiserror::Error;
+
+#[allow(clippy::enum_variant_names)]
+#[derive(Error, Debug)]
+pub enum ReconcilerError {
+    #[error("Key extraction failed: {details}")]
+    KeyError { details: String },
+
+    #[error("Property error for '{property}': {details}")]
+    PropError { property: String, details: String },
+
+    #[error("Serialization error: {0}")]
+    SerdeError(#[from] serde_json::Error),
+
+    #[error("Patch stream decoding failed: {details}")]
+    DecodeError { details: String },
+
+    #[error("HTML parse error at byte {position}: {details}")]
+    HtmlParseError { position: usize, details: String },
+}
@@ -0,0 +1,155 @@
+//! Framework callback props, the DOM events that trigger them and the
+//! attributes that wire them up in a stub
+use crate::markup::{html_escape, js_string_escape};
+use log::warn;
+use phf::phf_map;
+use std::collections::HashMap;
+
+/// A DOM event that triggers a framework callback
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DomEvent {
+    pub event: &'static str,
+    /// Only fire for this `KeyboardEvent.key`
+    pub key: Option<&'static str>,
+}
+
+const fn dom(event: &'static str) -> DomEvent {
+    DomEvent { event, key: None }
+}
+
+// Framework callback props (without the `Name` suffix) and the DOM events
+// that trigger them. `onChanged` is resolved per element in `dom_events`.
+static FRAMEWORK_EVENTS: phf::Map<&'static str, &'static [DomEvent]> = phf_map! {
+    "onPressed" => &[dom("click")],
+    "onTap" => &[dom("click")],
+    "onHover" => &[dom("mouseenter")],
+    "onSubmitted" => &[DomEvent { event: "keydown", key: Some("Enter") }],
+    "onDrag" => &[dom("pointerdown"), dom("pointermove"), dom("pointerup")],
+};
+
+/// DOM events for a framework callback on a `tag` element; empty for
+/// callbacks with no DOM counterpart
+pub fn dom_events(framework_event: &str, tag: &str) -> &'static [DomEvent] {
+    match framework_event {
+        // Text entry reports every keystroke; selects and custom elements
+        // only report committed values
+        "onChanged" if matches!(tag, "input" | "textarea") => &[DomEvent { event: "input", key: None }],
+        "onChanged" => &[DomEvent { event: "change", key: None }],
+        _ => FRAMEWORK_EVENTS.get(framework_event).copied().unwrap_or(&[]),
+    }
+}
+
+/// One handler on one element, as listed in `event_bindings`
+#[derive(Debug, Clone, PartialEq)]
+pub struct EventBinding {
+    pub html_id: String,
+    /// DOM event name, e.g. "click"
+    pub event: String,
+    /// Only fire for this `KeyboardEvent.key`
+    pub key: Option<String>,
+    pub callback: String,
+    /// The `<callback prop>Args` list, when non-empty
+    pub args: Option<serde_json::Value>,
+}
+
+/// Handlers for every `<event>Name` prop with a DOM counterpart, ordered by
+/// prop name; none while `enabled` is false. When two callbacks map to the
+/// same DOM event (onTap and onPressed) the first by name wins.
+pub fn event_bindings(html_id: &str, tag: &str, props: &HashMap<String, serde_json::Value>) -> Vec<EventBinding> {
+    if !props.get("enabled").and_then(|v| v.as_bool()).unwrap_or(true) {
+        return Vec::new();
+    }
+    let mut callbacks: Vec<(&str, &str)> = props.iter()
+        .filter_map(|(prop, value)| Some((prop.strip_suffix("Name")?, value.as_str()?)))
+        .collect();
+    callbacks.sort_unstable();
+
+    let mut bindings = Vec::new();
+    for (framework_event, callback) in callbacks {
+        let args = props.get(&format!("{}Args", framework_event))
+            .filter(|v| v.as_array().is_some_and(|arr| !arr.is_empty()));
+        for dom_event in dom_events(framework_event, tag) {
+            if bindings.iter().any(|b: &EventBinding| b.event == dom_event.event) {
+                warn!("'{}' binds '{}' more than once; keeping the first handler", html_id, dom_event.event);
+                continue;
+            }
+            bindings.push(EventBinding {
+                html_id: html_id.to_string(),
+                event: dom_event.event.to_string(),
+                key: dom_event.key.map(str::to_string),
+                callback: callback.to_string(),
+                args: args.cloned(),
+            });
+        }
+    }
+    bindings
+}
+
+/// How stubs wire event handlers to their callbacks
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum EventMode {
+    /// `onclick="handleClick('cb')"` and friends; needs `unsafe-inline`
+    /// under a CSP
+    #[default]
+    Inline,
+    /// `data-fw-on-<event>="cb"` plus `data-fw-args-<event>` and
+    /// `data-fw-key-<event>`, attached by client code from the result's
+    /// `event_bindings`
+    DataAttributes,
+}
+
+impl EventMode {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "inline" => Some(EventMode::Inline),
+            "data_attributes" => Some(EventMode::DataAttributes),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            EventMode::Inline => "inline",
+            EventMode::DataAttributes => "data_attributes",
+        }
+    }
+}
+
+/// Append the attributes wiring one event binding. Inline mode writes an
+/// `on<event>` handler calling the client's `handleClick`/`handleEvent`;
+/// data-attribute mode writes `data-fw-on-<event>` plus `-args-`/`-key-`
+/// attributes for client code to attach.
+pub fn push_event_attrs(attrs: &mut String, binding: &EventBinding, mode: EventMode) {
+    let event = &binding.event;
+    let args = binding.args.as_ref()
+        .map(|args| serde_json::to_string(args).unwrap_or_else(|_| "[]".to_string()));
+    match mode {
+        // Values become JS string literals first; the browser undoes the
+        // HTML escaping before the handler runs
+        EventMode::Inline => {
+            let callback = js_string_escape(&binding.callback);
+            let call = match event.as_str() {
+                // Clicks keep the original handlers so existing client code works
+                "click" => match &args {
+                    Some(args) => format!("handleClickWithArgs('{}', '{}')", callback, js_string_escape(args)),
+                    None => format!("handleClick('{}')", callback),
+                },
+                _ => format!("handleEvent(event, '{}', '{}')", callback, js_string_escape(args.as_deref().unwrap_or("[]"))),
+            };
+            let handler = match &binding.key {
+                Some(key) => format!("if (event.key === '{}') {}", js_string_escape(key), call),
+                None => call,
+            };
+            attrs.push_str(&format!(r#" on{}="{}""#, event, html_escape(&handler)));
+        }
+        EventMode::DataAttributes => {
+            attrs.push_str(&format!(r#" data-fw-on-{}="{}""#, event, html_escape(&binding.callback)));
+            if let Some(args) = args {
+                attrs.push_str(&format!(r#" data-fw-args-{}="{}""#, event, html_escape(&args)));
+            }
+            if let Some(key) = &binding.key {
+                attrs.push_str(&format!(r#" data-fw-key-{}="{}""#, event, html_escape(key)));
+            }
+        }
+    }
+}
@@ -0,0 +1,119 @@
+//! Extension point through which the diff engine calls back into its embedder
+use crate::errors::ReconcilerError;
+use crate::types::RustNodeData;
+
+/// Callbacks the diff engine needs from whatever owns the widget objects.
+///
+/// Every method has a default so a pure Rust caller can diff prebuilt node
+/// maps with `NullHost` and get the stored `html` back in INSERT/REPLACE
+/// patches.
+///
+/// Lifecycle hooks are called for every node once diffing is done, in
+/// three phases: `deactivate` (children before parents); then
+/// `init_state` + `did_change_dependencies`, `activate` and
+/// `did_update_widget` in tree order (parents before children); then
+/// `dispose` (children before parents). A failing hook is reported in
+/// `RustReconciliationResult::lifecycle_errors` and the rest still run.
+pub trait DiffHost {
+    /// Host handle stored in `RustNodeData::widget_instance`
+    type Widget: Clone;
+    /// Error type returned by host callbacks; core errors convert into it
+    type Error: From<ReconcilerError> + std::fmt::Display;
+
+    /// HTML for a node that is being inserted or that replaces another node
+    fn generate_html(&mut self, node: &RustNodeData<Self::Widget>) -> Result<String, Self::Error> {
+        Ok(node.html.clone())
+    }
+
+    /// Called for every inserted or updated node (CSS rules, callbacks, ...)
+    fn collect_details(&mut self, _node: &RustNodeData<Self::Widget>) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    /// Called instead of `collect_details` for the descendants of a memoized
+    /// node. They are copied unchanged from the previous tree, so a host may
+    /// replay what it collected for them then.
+    fn reuse_details(&mut self, node: &RustNodeData<Self::Widget>) -> Result<(), Self::Error> {
+        self.collect_details(node)
+    }
+
+    /// Called for a node mounted for the first time (inserted, or replacing
+    /// a node of another type)
+    fn init_state(&mut self, _node: &RustNodeData<Self::Widget>) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    /// Called right after a successful `init_state`
+    fn did_change_dependencies(&mut self, _node: &RustNodeData<Self::Widget>) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    /// Called for every old node that leaves its place in the tree, before
+    /// it is either disposed or activated under a new parent
+    fn deactivate(&mut self, _node: &RustNodeData<Self::Widget>) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    /// Called for a node re-inserted under a new parent, with its new data
+    fn activate(&mut self, _node: &RustNodeData<Self::Widget>) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    /// Called for a node updated in place
+    fn did_update_widget(
+        &mut self,
+        _old: &RustNodeData<Self::Widget>,
+        _new: &RustNodeData<Self::Widget>,
+    ) -> Result<(), Self::Error> {
+        Ok(())
+    }
+
+    /// Called once for every node that vanished from the tree or was
+    /// replaced by a node of another type, whether it got its own REMOVE
+    /// patch or left the DOM with an ancestor
+    fn dispose(&mut self, _node: &RustNodeData<Self::Widget>) -> Result<(), Self::Error> {
+        Ok(())
+    }
+}
+
+/// A lifecycle hook, named as on the Python `State` class
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LifecycleHook {
+    InitState,
+    DidChangeDependencies,
+    DidUpdateWidget,
+    Deactivate,
+    Activate,
+    Dispose,
+}
+
+impl std::fmt::Display for LifecycleHook {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            LifecycleHook::InitState => "initState",
+            LifecycleHook::DidChangeDependencies => "didChangeDependencies",
+            LifecycleHook::DidUpdateWidget => "didUpdateWidget",
+            LifecycleHook::Deactivate => "deactivate",
+            LifecycleHook::Activate => "activate",
+            LifecycleHook::Dispose => "dispose",
+        })
+    }
+}
+
+/// A lifecycle hook that failed
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LifecycleError {
+    pub hook: LifecycleHook,
+    pub key: String,
+    pub html_id: String,
+    pub message: String,
+}
+
+/// Host for callers without widget objects: uses the prebuilt `html` as-is
+#[derive(Debug, Default, Clone, Copy)]
+pub struct NullHost;
+
+impl DiffHost for NullHost {
+    type Widget = ();
+    type Error = ReconcilerError;
+}
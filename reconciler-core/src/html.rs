@@ -0,0 +1,340 @@
+//! Minimal HTML tokenizer and fragment parser for markup produced by the
+//! stub generators
+//!
+//! Comments, doctypes and processing instructions are skipped, and the
+//! contents of `<script>`/`<style>` are kept as raw text.
+use crate::errors::ReconcilerError;
+
+/// Elements that never have children or an end tag
+pub const VOID_ELEMENTS: &[&str] = &[
+    "area", "base", "br", "col", "embed", "hr", "img", "input",
+    "link", "meta", "param", "source", "track", "wbr",
+];
+
+pub fn is_void_element(tag: &str) -> bool {
+    VOID_ELEMENTS.contains(&tag)
+}
+
+/// One start tag with its attributes in source order
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StartTag {
+    /// Lowercased tag name
+    pub name: String,
+    /// Lowercased attribute names with entity-decoded values; valueless
+    /// attributes have an empty value
+    pub attrs: Vec<(String, String)>,
+}
+
+impl StartTag {
+    pub fn attr(&self, name: &str) -> Option<&str> {
+        self.attrs.iter().find(|(n, _)| n == name).map(|(_, v)| v.as_str())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Start { tag: StartTag, self_closing: bool },
+    End(String),
+    Text(String),
+}
+
+/// Tokens of `html` with the byte offset each one starts at
+fn tokenize(html: &str) -> Vec<(usize, Token)> {
+    let bytes = html.as_bytes();
+    let mut tokens = Vec::new();
+    let mut pos = 0;
+    while pos < bytes.len() {
+        let Some(offset) = html[pos..].find('<') else {
+            tokens.push((pos, Token::Text(decode_entities(&html[pos..]))));
+            break;
+        };
+        if offset > 0 {
+            tokens.push((pos, Token::Text(decode_entities(&html[pos..pos + offset]))));
+        }
+        let start = pos + offset;
+        pos = start + 1;
+        let rest = &html[pos..];
+        if rest.starts_with("!--") {
+            pos += rest.find("-->").map_or(rest.len(), |end| end + 3);
+            continue;
+        }
+        if let Some(end_tag) = rest.strip_prefix('/') {
+            let name_len = end_tag.bytes().take_while(|b| b.is_ascii_alphanumeric() || *b == b'-').count();
+            tokens.push((start, Token::End(end_tag[..name_len].to_ascii_lowercase())));
+            pos += rest.find('>').map_or(rest.len(), |end| end + 1);
+            continue;
+        }
+        if rest.starts_with('!') || rest.starts_with('?') {
+            pos += rest.find('>').map_or(rest.len(), |end| end + 1);
+            continue;
+        }
+        let name_len = rest.bytes().take_while(|b| b.is_ascii_alphanumeric() || *b == b'-').count();
+        if name_len == 0 {
+            // A lone '<' is text
+            tokens.push((start, Token::Text("<".to_string())));
+            continue;
+        }
+        let name = rest[..name_len].to_ascii_lowercase();
+        pos += name_len;
+
+        let mut attrs = Vec::new();
+        let mut self_closing = false;
+        loop {
+            while pos < bytes.len() && bytes[pos].is_ascii_whitespace() {
+                pos += 1;
+            }
+            if pos >= bytes.len() {
+                break;
+            }
+            match bytes[pos] {
+                b'>' => {
+                    pos += 1;
+                    break;
+                }
+                b'/' => {
+                    self_closing = bytes.get(pos + 1) == Some(&b'>');
+                    pos += 1;
+                    continue;
+                }
+                _ => {}
+            }
+            let attr_start = pos;
+            while pos < bytes.len() && !bytes[pos].is_ascii_whitespace() && !matches!(bytes[pos], b'=' | b'>' | b'/') {
+                pos += 1;
+            }
+            let attr_name = html[attr_start..pos].to_ascii_lowercase();
+            let mut value = String::new();
+            if pos < bytes.len() && bytes[pos] == b'=' {
+                pos += 1;
+                let (raw, next) = match bytes.get(pos) {
+                    Some(&quote @ (b'"' | b'\'')) => {
+                        let end = html[pos + 1..].find(quote as char).map_or(bytes.len(), |e| pos + 1 + e);
+                        (&html[pos + 1..end], (end + 1).min(bytes.len()))
+                    }
+                    _ => {
+                        let end = html[pos..].find(|c: char| c.is_ascii_whitespace() || c == '>').map_or(bytes.len(), |e| pos + e);
+                        (&html[pos..end], end)
+                    }
+                };
+                value = decode_entities(raw);
+                pos = next;
+            }
+            if !attr_name.is_empty() {
+                attrs.push((attr_name, value));
+            } else if pos == attr_start {
+                // Nothing consumed: skip a whole char so the scan always advances
+                pos += html[pos..].chars().next().map_or(1, char::len_utf8);
+            }
+        }
+
+        let raw_text = matches!(name.as_str(), "script" | "style");
+        let close = format!("</{}", name);
+        tokens.push((start, Token::Start { tag: StartTag { name, attrs }, self_closing }));
+        if raw_text {
+            let len = html[pos..].to_ascii_lowercase().find(&close).unwrap_or(html.len() - pos);
+            if len > 0 {
+                tokens.push((pos, Token::Text(html[pos..pos + len].to_string())));
+            }
+            pos += len;
+        }
+    }
+    tokens
+}
+
+/// All start tags of `html` in document order
+pub fn scan_start_tags(html: &str) -> Vec<StartTag> {
+    tokenize(html).into_iter()
+        .filter_map(|(_, token)| match token {
+            Token::Start { tag, .. } => Some(tag),
+            _ => None,
+        })
+        .collect()
+}
+
+/// A parsed element with its children
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Element {
+    pub tag: StartTag,
+    pub children: Vec<HtmlNode>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HtmlNode {
+    Element(Element),
+    Text(String),
+}
+
+impl Element {
+    pub fn attr(&self, name: &str) -> Option<&str> {
+        self.tag.attr(name)
+    }
+
+    pub fn classes(&self) -> Vec<&str> {
+        self.attr("class").unwrap_or("").split_whitespace().collect()
+    }
+
+    /// `style` declarations as (property, value) pairs in source order.
+    /// Semicolons that are backslash-escaped or quoted do not end a
+    /// declaration; values are returned as written.
+    pub fn style(&self) -> Vec<(&str, &str)> {
+        split_declarations(self.attr("style").unwrap_or(""))
+            .into_iter()
+            .filter_map(|decl| decl.split_once(':'))
+            .map(|(k, v)| (k.trim(), v.trim()))
+            .filter(|(k, _)| !k.is_empty())
+            .collect()
+    }
+
+    /// Concatenated text of the direct text children
+    pub fn text(&self) -> String {
+        self.children.iter()
+            .filter_map(|c| match c {
+                HtmlNode::Text(t) => Some(t.as_str()),
+                HtmlNode::Element(_) => None,
+            })
+            .collect()
+    }
+}
+
+fn split_declarations(style: &str) -> Vec<&str> {
+    let mut declarations = Vec::new();
+    let mut start = 0;
+    let mut quote = None;
+    let mut escaped = false;
+    for (i, c) in style.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '"' | '\'' if quote == Some(c) => quote = None,
+            '"' | '\'' if quote.is_none() => quote = Some(c),
+            ';' if quote.is_none() => {
+                declarations.push(&style[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    declarations.push(&style[start..]);
+    declarations
+}
+
+/// Parse a fragment into its top-level nodes. End tags must match the open
+/// element; void elements and `/>` need none.
+pub fn parse_fragment(html: &str) -> Result<Vec<HtmlNode>, ReconcilerError> {
+    // Open elements, innermost last, with the byte offset of their start tag
+    let mut open: Vec<(usize, Element)> = Vec::new();
+    let mut top = Vec::new();
+    let push = |open: &mut Vec<(usize, Element)>, top: &mut Vec<HtmlNode>, node: HtmlNode| match open.last_mut() {
+        Some((_, parent)) => parent.children.push(node),
+        None => top.push(node),
+    };
+
+    for (position, token) in tokenize(html) {
+        match token {
+            Token::Text(text) => push(&mut open, &mut top, HtmlNode::Text(text)),
+            Token::Start { tag, self_closing } => {
+                let element = Element { tag, children: Vec::new() };
+                if self_closing || is_void_element(&element.tag.name) {
+                    push(&mut open, &mut top, HtmlNode::Element(element));
+                } else {
+                    open.push((position, element));
+                }
+            }
+            Token::End(name) => {
+                if is_void_element(&name) {
+                    continue;
+                }
+                match open.pop() {
+                    Some((_, element)) if element.tag.name == name => {
+                        push(&mut open, &mut top, HtmlNode::Element(element));
+                    }
+                    Some((_, element)) => {
+                        return Err(ReconcilerError::HtmlParseError {
+                            position,
+                            details: format!("</{}> closes <{}>", name, element.tag.name),
+                        });
+                    }
+                    None => {
+                        return Err(ReconcilerError::HtmlParseError {
+                            position,
+                            details: format!("</{}> without an open element", name),
+                        });
+                    }
+                }
+            }
+        }
+    }
+
+    match open.pop() {
+        Some((position, element)) => Err(ReconcilerError::HtmlParseError {
+            position,
+            details: format!("<{}> is never closed", element.tag.name),
+        }),
+        None => Ok(top),
+    }
+}
+
+/// Parse markup that must consist of exactly one element (whitespace
+/// around it is ignored), as every stub does
+pub fn parse_single_element(html: &str) -> Result<Element, ReconcilerError> {
+    let mut elements = Vec::new();
+    for node in parse_fragment(html)? {
+        match node {
+            HtmlNode::Element(e) => elements.push(e),
+            HtmlNode::Text(t) if t.trim().is_empty() => {}
+            HtmlNode::Text(t) => {
+                return Err(ReconcilerError::HtmlParseError {
+                    position: 0,
+                    details: format!("text {:?} outside the element", t),
+                });
+            }
+        }
+    }
+    match elements.len() {
+        1 => Ok(elements.remove(0)),
+        n => Err(ReconcilerError::HtmlParseError {
+            position: 0,
+            details: format!("expected one top-level element, found {}", n),
+        }),
+    }
+}
+
+/// Decode the entities `html_escape` produces plus numeric references
+pub fn decode_entities(s: &str) -> String {
+    if !s.contains('&') {
+        return s.to_string();
+    }
+    let mut out = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(amp) = rest.find('&') {
+        out.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+        let decoded = rest.find(';').and_then(|semi| {
+            let entity = &rest[1..semi];
+            let ch = match entity {
+                "amp" => Some('&'),
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "quot" => Some('"'),
+                "apos" => Some('\''),
+                _ => entity.strip_prefix("#x").or_else(|| entity.strip_prefix("#X"))
+                    .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                    .or_else(|| entity.strip_prefix('#').and_then(|dec| dec.parse().ok()))
+                    .and_then(char::from_u32),
+            };
+            ch.map(|c| (c, semi + 1))
+        });
+        match decoded {
+            Some((c, len)) => {
+                out.push(c);
+                rest = &rest[len..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
@@ -0,0 +1,131 @@
+//! html_id generation: the process-global counter plus per-reconciler strategies
+use crate::errors::ReconcilerError;
+use crate::subtree_hash::{Fnv1a, write_str};
+use once_cell::sync::Lazy;
+use std::hash::Hasher;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Prefix of every generated id unless a caller supplies another one
+pub const DEFAULT_ID_PREFIX: &str = "fw_id_";
+
+/// Letters, digits, '-' and '_', starting with a letter, so generated ids
+/// are safe inside `id="..."` and in CSS selectors
+pub fn is_valid_id_prefix(prefix: &str) -> bool {
+    prefix.starts_with(|c: char| c.is_ascii_alphabetic())
+        && prefix.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+}
+
+/// Global ID generator (lock-free, atomic)
+static ID_COUNTER: Lazy<AtomicUsize> = Lazy::new(|| {
+    AtomicUsize::new(0)
+});
+
+pub fn next_id() -> String {
+    let id = ID_COUNTER.fetch_add(1, Ordering::SeqCst);
+    format!("{}{}", DEFAULT_ID_PREFIX, id)
+}
+
+/// How an [`IdGenerator`] mints new html_ids
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum IdStrategy {
+    /// Shared process-wide counter (`next_id`); ids depend on every
+    /// reconciler that ran before
+    #[default]
+    Global,
+    /// Counter owned by the generator, restarted by [`IdGenerator::reset`]
+    Counter,
+    /// Hash of the key path from the root, identical across runs and
+    /// processes for the same tree
+    KeyPath,
+}
+
+impl FromStr for IdStrategy {
+    type Err = ReconcilerError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "global" => Ok(IdStrategy::Global),
+            "counter" => Ok(IdStrategy::Counter),
+            "key_path" => Ok(IdStrategy::KeyPath),
+            other => Err(ReconcilerError::PropError {
+                property: "id_strategy".to_string(),
+                details: format!("unknown strategy '{}', expected 'global', 'counter' or 'key_path'", other),
+            }),
+        }
+    }
+}
+
+/// Per-reconciler source of html_ids
+#[derive(Debug)]
+pub struct IdGenerator {
+    strategy: IdStrategy,
+    prefix: String,
+    counter: AtomicUsize,
+}
+
+impl Default for IdGenerator {
+    fn default() -> Self {
+        IdGenerator::new(IdStrategy::default(), DEFAULT_ID_PREFIX)
+    }
+}
+
+impl IdGenerator {
+    pub fn new(strategy: IdStrategy, prefix: impl Into<String>) -> Self {
+        IdGenerator { strategy, prefix: prefix.into(), counter: AtomicUsize::new(0) }
+    }
+
+    pub fn strategy(&self) -> IdStrategy {
+        self.strategy
+    }
+
+    pub fn prefix(&self) -> &str {
+        &self.prefix
+    }
+
+    /// Mint an id. `key_path` (keys from the root down to the node) is only
+    /// evaluated for [`IdStrategy::KeyPath`].
+    pub fn next_id(&self, key_path: impl FnOnce() -> Vec<String>) -> String {
+        let suffix = match self.strategy {
+            IdStrategy::Global => ID_COUNTER.fetch_add(1, Ordering::SeqCst).to_string(),
+            IdStrategy::Counter => self.counter.fetch_add(1, Ordering::SeqCst).to_string(),
+            IdStrategy::KeyPath => format!("{:016x}", hash_key_path(&key_path())),
+        };
+        format!("{}{}", self.prefix, suffix)
+    }
+
+    /// Whether `id` has the shape this generator mints: the prefix followed
+    /// by a counter or hash (so derived ids like `fw_id_3_viewport` are not)
+    pub fn is_generated(&self, id: &str) -> bool {
+        id.strip_prefix(self.prefix.as_str())
+            .is_some_and(|rest| !rest.is_empty() && rest.bytes().all(|b| b.is_ascii_alphanumeric()))
+    }
+
+    /// Note an id minted elsewhere (e.g. adopted from server-rendered
+    /// markup) so counter strategies never hand it out again
+    pub fn observe(&self, id: &str) {
+        let Some(n) = id.strip_prefix(self.prefix.as_str()).and_then(|n| n.parse::<usize>().ok()) else {
+            return;
+        };
+        match self.strategy {
+            IdStrategy::Global => ID_COUNTER.fetch_max(n + 1, Ordering::SeqCst),
+            IdStrategy::Counter => self.counter.fetch_max(n + 1, Ordering::SeqCst),
+            IdStrategy::KeyPath => return,
+        };
+    }
+
+    /// Restart the per-instance counter (no effect on the other strategies)
+    pub fn reset(&self) {
+        self.counter.store(0, Ordering::SeqCst);
+    }
+}
+
+/// Keys may contain '/' (disambiguated keys do), so each one is
+/// length-prefixed rather than joined with a separator
+fn hash_key_path(path: &[String]) -> u64 {
+    let mut hasher = Fnv1a::default();
+    for key in path {
+        write_str(&mut hasher, key);
+    }
+    hasher.finish()
+}
@@ -0,0 +1,30 @@
+//! Widget key helpers shared by tree builders
+use crate::types::NodeMap;
+
+/// `start` followed by each of its ancestors, as found by `parent`. Stops
+/// after `bound + 1` items, so a cycle among at most `bound` entries
+/// cannot loop forever.
+pub fn parent_chain<'a>(
+    start: Option<&'a str>,
+    bound: usize,
+    parent: impl Fn(&'a str) -> Option<&'a str>,
+) -> impl Iterator<Item = &'a str> {
+    std::iter::successors(start, move |&k| parent(k)).take(bound + 1)
+}
+
+/// `key` followed by its ancestors in `map`, following `parent_key`
+pub fn ancestor_keys<'a, W>(map: &'a NodeMap<W>, key: Option<&'a str>) -> impl Iterator<Item = &'a str> {
+    parent_chain(key, map.len(), |k| map.get(k).and_then(|n| n.parent_key.as_deref()))
+}
+
+/// Keys from the root down to `key` (inclusive), following `parent_key`
+pub fn key_path<W>(map: &NodeMap<W>, key: Option<&str>) -> Vec<String> {
+    let mut path: Vec<String> = ancestor_keys(map, key).map(str::to_string).collect();
+    path.reverse();
+    path
+}
+
+/// Replacement for a key that is already taken: `<parent key>/<key>#<sibling index>`
+pub fn disambiguated_key(parent_key: &str, key: &str, sibling_index: usize) -> String {
+    format!("{}/{}#{}", parent_key, key, sibling_index)
+}
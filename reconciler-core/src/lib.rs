@@ -0,0 +1,31 @@
+//! Interpreter-free reconciliation core: node maps, keyed child diffing
+//! with LIS move minimisation, prop diffing and patch generation.
+//!
+//! Embedders plug in through [`DiffHost`]; the PyO3 bindings in the
+//! `rust_reconciler` crate are one such host.
+pub mod diff_engine;
+pub mod errors;
+pub mod events;
+pub mod host;
+pub mod html;
+pub mod ids;
+pub mod keys;
+pub mod markup;
+#[cfg(feature = "msgpack")]
+pub mod msgpack;
+pub mod prop_rules;
+pub mod render;
+pub mod stats;
+pub mod subtree_hash;
+pub mod types;
+
+pub use diff_engine::{DiffEngine, DiffOptions, PropDiff, diff_props, find_root_key, longest_increasing_subsequence, reconcile, removes_first, reorder_patches_parent_first};
+pub use errors::ReconcilerError;
+pub use host::{DiffHost, LifecycleError, LifecycleHook, NullHost};
+pub use ids::{DEFAULT_ID_PREFIX, IdGenerator, IdStrategy, is_valid_id_prefix, next_id};
+pub use keys::{ancestor_keys, disambiguated_key, key_path, parent_chain};
+pub use prop_rules::{CompareFn, DEFAULT_IGNORED_PROPS, PropComparator, PropDiffRules};
+pub use render::{RenderChunks, render_to_string, split_stub};
+pub use stats::ReconcileStats;
+pub use subtree_hash::compute_subtree_hashes;
+pub use types::{JsInitializer, NodeMap, PatchAction, PatchPayload, RustNodeData, RustPatch, RustReconciliationResult, is_renderable_type, patches_to_json_bytes};
@@ -0,0 +1,74 @@
+//! Name checks and escaping for markup the stub generators emit
+//!
+//! Everything here is pure string handling so templates, generated stubs
+//! and the tag registry all apply the same rules.
+
+/// Letters, digits and '-', starting with a letter
+pub fn is_valid_tag_name(tag: &str) -> bool {
+    tag.starts_with(|c: char| c.is_ascii_alphabetic())
+        && tag.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+}
+
+/// Non-empty, without whitespace, quotes, '=', '<', '>' or '/'
+pub fn is_valid_attribute_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name.chars().any(|c| c.is_whitespace() || c.is_control() || matches!(c, '"' | '\'' | '=' | '<' | '>' | '/'))
+}
+
+/// `on*` attributes run script; only the generator's own handlers may set them
+pub fn is_event_handler_attribute(name: &str) -> bool {
+    name.get(..2).is_some_and(|prefix| prefix.eq_ignore_ascii_case("on"))
+}
+
+/// CSS property names: letters, digits and '-' (covers `--custom` properties)
+pub fn is_valid_css_property(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+}
+
+/// Keep a value inside its declaration: backslash-escape `\ ; { }` and
+/// drop control characters
+pub fn css_escape_value(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' | ';' | '{' | '}' => {
+                out.push('\\');
+                out.push(c);
+            }
+            c if c.is_control() => {}
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// Body of a single- or double-quoted JS string literal: quotes,
+/// backslashes, line terminators and `<` (so `</script>` cannot appear)
+/// are escaped
+pub fn js_string_escape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '\\' => out.push_str("\\\\"),
+            '\'' => out.push_str("\\'"),
+            '"' => out.push_str("\\\""),
+            '<' => out.push_str("\\x3C"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\u{2028}' => out.push_str("\\u2028"),
+            '\u{2029}' => out.push_str("\\u2029"),
+            c if c.is_control() => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// Consistent HTML attribute escaping, also used for element text
+pub fn html_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#x27;")
+}
@@ -0,0 +1,376 @@
+//! Compact MessagePack encoding of the patch stream (feature `msgpack`)
+//!
+//! Schema (all positional, no field names on the wire):
+//!
+//! ```text
+//! payload     = [version: uint, patches: [patch...], initializers: [init...]]
+//! patch       = [action: uint, html_id: str, data: value]
+//! init        = [type: str, target_id: str, data: value, before_id: str | nil]
+//! action      = 0 INSERT | 1 REMOVE | 2 UPDATE | 3 MOVE | 4 REPLACE
+//! value       = nil | bool | int | float64 | str | [value...] | {str: value...}
+//! ```
+//!
+//! `version` is currently `1`. Integers use the smallest MessagePack int
+//! form that holds them; floats are always float64 (float32 is accepted
+//! when decoding).
+use crate::errors::ReconcilerError;
+use crate::types::{JsInitializer, PatchAction, PatchPayload, RustPatch};
+use serde_json::{Map, Number, Value};
+
+/// Wire schema version written as the first payload element
+pub const SCHEMA_VERSION: u64 = 1;
+
+/// Deepest array/map nesting `decode_payload` accepts, so hostile input
+/// fails with `DecodeError` instead of overflowing the stack
+pub const MAX_DEPTH: usize = 128;
+
+fn action_code(action: &PatchAction) -> u64 {
+    match action {
+        PatchAction::Insert => 0,
+        PatchAction::Remove => 1,
+        PatchAction::Update => 2,
+        PatchAction::Move => 3,
+        PatchAction::Replace => 4,
+    }
+}
+
+fn action_from_code(code: u64) -> Option<PatchAction> {
+    Some(match code {
+        0 => PatchAction::Insert,
+        1 => PatchAction::Remove,
+        2 => PatchAction::Update,
+        3 => PatchAction::Move,
+        4 => PatchAction::Replace,
+        _ => return None,
+    })
+}
+
+/// Encode patches and initializers into a MessagePack buffer
+pub fn encode_payload(patches: &[RustPatch], js_initializers: &[JsInitializer]) -> Vec<u8> {
+    let mut buf = Vec::new();
+    write_array_len(&mut buf, 3);
+    write_uint(&mut buf, SCHEMA_VERSION);
+
+    write_array_len(&mut buf, patches.len());
+    for patch in patches {
+        write_array_len(&mut buf, 3);
+        write_uint(&mut buf, action_code(&patch.action));
+        write_str(&mut buf, &patch.html_id);
+        write_value(&mut buf, &patch.data);
+    }
+
+    write_array_len(&mut buf, js_initializers.len());
+    for init in js_initializers {
+        write_array_len(&mut buf, 4);
+        write_str(&mut buf, &init.init_type);
+        write_str(&mut buf, &init.target_id);
+        write_value(&mut buf, &init.data);
+        match init.before_id {
+            Some(ref id) => write_str(&mut buf, id),
+            None => buf.push(0xc0),
+        }
+    }
+    buf
+}
+
+/// Decode a buffer produced by [`encode_payload`]
+pub fn decode_payload(bytes: &[u8]) -> Result<PatchPayload, ReconcilerError> {
+    let mut reader = Reader { bytes, pos: 0, depth: 0 };
+    let root = reader.read_value()?;
+    if reader.pos != bytes.len() {
+        return Err(reader.error("trailing bytes after payload"));
+    }
+
+    let mut root = into_array(root, Some(3), "payload")?.into_iter();
+    let version = root.next().and_then(|v| v.as_u64());
+    if version != Some(SCHEMA_VERSION) {
+        return Err(ReconcilerError::DecodeError {
+            details: format!("unsupported schema version {:?}", version),
+        });
+    }
+
+    let patches = into_array(root.next().unwrap_or_default(), None, "patches")?
+        .into_iter()
+        .map(|p| {
+            let mut fields = into_array(p, Some(3), "patch")?.into_iter();
+            let action = fields.next().and_then(|v| v.as_u64()).and_then(action_from_code)
+                .ok_or_else(|| ReconcilerError::DecodeError { details: "invalid patch action".into() })?;
+            let html_id = into_string(fields.next().unwrap_or_default(), "patch html_id")?;
+            let data = fields.next().unwrap_or_default();
+            Ok(RustPatch { action, html_id, data })
+        })
+        .collect::<Result<Vec<_>, ReconcilerError>>()?;
+
+    let js_initializers = into_array(root.next().unwrap_or_default(), None, "initializers")?
+        .into_iter()
+        .map(|i| {
+            let mut fields = into_array(i, Some(4), "initializer")?.into_iter();
+            let init_type = into_string(fields.next().unwrap_or_default(), "initializer type")?;
+            let target_id = into_string(fields.next().unwrap_or_default(), "initializer target_id")?;
+            let data = fields.next().unwrap_or_default();
+            let before_id = match fields.next().unwrap_or_default() {
+                Value::Null => None,
+                other => Some(into_string(other, "initializer before_id")?),
+            };
+            Ok(JsInitializer { init_type, target_id, data, before_id })
+        })
+        .collect::<Result<Vec<_>, ReconcilerError>>()?;
+
+    Ok(PatchPayload { patches, js_initializers })
+}
+
+/// Unwrap an array, checking its length when one is expected
+fn into_array(value: Value, expected_len: Option<usize>, what: &str) -> Result<Vec<Value>, ReconcilerError> {
+    match value {
+        Value::Array(items) if expected_len.is_none_or(|len| items.len() == len) => Ok(items),
+        other => Err(ReconcilerError::DecodeError {
+            details: format!("{} is not a well-formed array: {}", what, other),
+        }),
+    }
+}
+
+fn into_string(value: Value, what: &str) -> Result<String, ReconcilerError> {
+    match value {
+        Value::String(s) => Ok(s),
+        other => Err(ReconcilerError::DecodeError {
+            details: format!("{} is not a string: {}", what, other),
+        }),
+    }
+}
+
+fn write_uint(buf: &mut Vec<u8>, n: u64) {
+    if n < 0x80 {
+        buf.push(n as u8);
+    } else if n <= u8::MAX as u64 {
+        buf.push(0xcc);
+        buf.push(n as u8);
+    } else if n <= u16::MAX as u64 {
+        buf.push(0xcd);
+        buf.extend_from_slice(&(n as u16).to_be_bytes());
+    } else if n <= u32::MAX as u64 {
+        buf.push(0xce);
+        buf.extend_from_slice(&(n as u32).to_be_bytes());
+    } else {
+        buf.push(0xcf);
+        buf.extend_from_slice(&n.to_be_bytes());
+    }
+}
+
+fn write_int(buf: &mut Vec<u8>, n: i64) {
+    if n >= 0 {
+        write_uint(buf, n as u64);
+    } else if n >= -32 {
+        buf.push(n as i8 as u8);
+    } else if n >= i8::MIN as i64 {
+        buf.push(0xd0);
+        buf.push(n as i8 as u8);
+    } else if n >= i16::MIN as i64 {
+        buf.push(0xd1);
+        buf.extend_from_slice(&(n as i16).to_be_bytes());
+    } else if n >= i32::MIN as i64 {
+        buf.push(0xd2);
+        buf.extend_from_slice(&(n as i32).to_be_bytes());
+    } else {
+        buf.push(0xd3);
+        buf.extend_from_slice(&n.to_be_bytes());
+    }
+}
+
+fn write_str(buf: &mut Vec<u8>, s: &str) {
+    let len = s.len();
+    if len < 32 {
+        buf.push(0xa0 | len as u8);
+    } else if len <= u8::MAX as usize {
+        buf.push(0xd9);
+        buf.push(len as u8);
+    } else if len <= u16::MAX as usize {
+        buf.push(0xda);
+        buf.extend_from_slice(&(len as u16).to_be_bytes());
+    } else {
+        buf.push(0xdb);
+        buf.extend_from_slice(&(len as u32).to_be_bytes());
+    }
+    buf.extend_from_slice(s.as_bytes());
+}
+
+fn write_array_len(buf: &mut Vec<u8>, len: usize) {
+    if len < 16 {
+        buf.push(0x90 | len as u8);
+    } else if len <= u16::MAX as usize {
+        buf.push(0xdc);
+        buf.extend_from_slice(&(len as u16).to_be_bytes());
+    } else {
+        buf.push(0xdd);
+        buf.extend_from_slice(&(len as u32).to_be_bytes());
+    }
+}
+
+fn write_map_len(buf: &mut Vec<u8>, len: usize) {
+    if len < 16 {
+        buf.push(0x80 | len as u8);
+    } else if len <= u16::MAX as usize {
+        buf.push(0xde);
+        buf.extend_from_slice(&(len as u16).to_be_bytes());
+    } else {
+        buf.push(0xdf);
+        buf.extend_from_slice(&(len as u32).to_be_bytes());
+    }
+}
+
+fn write_value(buf: &mut Vec<u8>, value: &Value) {
+    match value {
+        Value::Null => buf.push(0xc0),
+        Value::Bool(false) => buf.push(0xc2),
+        Value::Bool(true) => buf.push(0xc3),
+        Value::Number(n) => {
+            if let Some(u) = n.as_u64() {
+                write_uint(buf, u);
+            } else if let Some(i) = n.as_i64() {
+                write_int(buf, i);
+            } else {
+                buf.push(0xcb);
+                buf.extend_from_slice(&n.as_f64().unwrap_or(0.0).to_be_bytes());
+            }
+        }
+        Value::String(s) => write_str(buf, s),
+        Value::Array(items) => {
+            write_array_len(buf, items.len());
+            for item in items {
+                write_value(buf, item);
+            }
+        }
+        Value::Object(map) => {
+            write_map_len(buf, map.len());
+            for (k, v) in map {
+                write_str(buf, k);
+                write_value(buf, v);
+            }
+        }
+    }
+}
+
+fn float_value(f: f64) -> Value {
+    Number::from_f64(f).map(Value::Number).unwrap_or(Value::Null)
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+    /// Arrays and maps currently open
+    depth: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn error(&self, details: &str) -> ReconcilerError {
+        ReconcilerError::DecodeError {
+            details: format!("{} at byte {}", details, self.pos),
+        }
+    }
+
+    fn take(&mut self, n: usize) -> Result<&'a [u8], ReconcilerError> {
+        let end = self.pos.checked_add(n).filter(|&end| end <= self.bytes.len())
+            .ok_or_else(|| self.error("unexpected end of input"))?;
+        let slice = &self.bytes[self.pos..end];
+        self.pos = end;
+        Ok(slice)
+    }
+
+    fn be<const N: usize>(&mut self) -> Result<[u8; N], ReconcilerError> {
+        let mut out = [0u8; N];
+        out.copy_from_slice(self.take(N)?);
+        Ok(out)
+    }
+
+    fn read_str(&mut self, len: usize) -> Result<Value, ReconcilerError> {
+        let raw = self.take(len)?;
+        std::str::from_utf8(raw)
+            .map(|s| Value::String(s.to_string()))
+            .map_err(|_| self.error("invalid UTF-8 in string"))
+    }
+
+    fn enter(&mut self) -> Result<(), ReconcilerError> {
+        if self.depth >= MAX_DEPTH {
+            return Err(self.error(&format!("nesting deeper than {}", MAX_DEPTH)));
+        }
+        self.depth += 1;
+        Ok(())
+    }
+
+    fn read_array(&mut self, len: usize) -> Result<Value, ReconcilerError> {
+        self.enter()?;
+        let mut items = Vec::with_capacity(len.min(self.bytes.len()));
+        for _ in 0..len {
+            items.push(self.read_value()?);
+        }
+        self.depth -= 1;
+        Ok(Value::Array(items))
+    }
+
+    fn read_map(&mut self, len: usize) -> Result<Value, ReconcilerError> {
+        self.enter()?;
+        let mut map = Map::new();
+        for _ in 0..len {
+            let key = match self.read_value()? {
+                Value::String(s) => s,
+                _ => return Err(self.error("map key is not a string")),
+            };
+            let value = self.read_value()?;
+            map.insert(key, value);
+        }
+        self.depth -= 1;
+        Ok(Value::Object(map))
+    }
+
+    fn read_value(&mut self) -> Result<Value, ReconcilerError> {
+        let marker = self.be::<1>()?[0];
+        match marker {
+            0x00..=0x7f => Ok(Value::from(marker)),
+            0x80..=0x8f => self.read_map((marker & 0x0f) as usize),
+            0x90..=0x9f => self.read_array((marker & 0x0f) as usize),
+            0xa0..=0xbf => self.read_str((marker & 0x1f) as usize),
+            0xc0 => Ok(Value::Null),
+            0xc2 => Ok(Value::Bool(false)),
+            0xc3 => Ok(Value::Bool(true)),
+            0xca => Ok(float_value(f32::from_be_bytes(self.be()?) as f64)),
+            0xcb => Ok(float_value(f64::from_be_bytes(self.be()?))),
+            0xcc => Ok(Value::from(self.be::<1>()?[0])),
+            0xcd => Ok(Value::from(u16::from_be_bytes(self.be()?))),
+            0xce => Ok(Value::from(u32::from_be_bytes(self.be()?))),
+            0xcf => Ok(Value::from(u64::from_be_bytes(self.be()?))),
+            0xd0 => Ok(Value::from(i8::from_be_bytes(self.be()?))),
+            0xd1 => Ok(Value::from(i16::from_be_bytes(self.be()?))),
+            0xd2 => Ok(Value::from(i32::from_be_bytes(self.be()?))),
+            0xd3 => Ok(Value::from(i64::from_be_bytes(self.be()?))),
+            0xd9 => {
+                let len = self.be::<1>()?[0] as usize;
+                self.read_str(len)
+            }
+            0xda => {
+                let len = u16::from_be_bytes(self.be()?) as usize;
+                self.read_str(len)
+            }
+            0xdb => {
+                let len = u32::from_be_bytes(self.be()?) as usize;
+                self.read_str(len)
+            }
+            0xdc => {
+                let len = u16::from_be_bytes(self.be()?) as usize;
+                self.read_array(len)
+            }
+            0xdd => {
+                let len = u32::from_be_bytes(self.be()?) as usize;
+                self.read_array(len)
+            }
+            0xde => {
+                let len = u16::from_be_bytes(self.be()?) as usize;
+                self.read_map(len)
+            }
+            0xdf => {
+                let len = u32::from_be_bytes(self.be()?) as usize;
+                self.read_map(len)
+            }
+            0xe0..=0xff => Ok(Value::from(marker as i8)),
+            _ => Err(self.error(&format!("unsupported marker 0x{:02x}", marker))),
+        }
+    }
+}
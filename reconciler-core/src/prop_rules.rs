@@ -0,0 +1,177 @@
+//! Which props `update_node` compares, and how
+//!
+//! Rules are layered: props ignored for every widget plus extra ones per
+//! widget type, and comparators per prop name with per-type overrides.
+//! Anything without a comparator is compared with `==`.
+use crate::diff_engine::PropDiff;
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+/// Props skipped by default: the widget handle, builders and callables
+pub const DEFAULT_IGNORED_PROPS: &[&str] = &[
+    "widget_instance", "itemBuilder", "onChanged", "onPressed", "onTap", "onDrag",
+];
+
+/// Embedder-supplied equality, e.g. a Python callable
+pub type CompareFn = Arc<dyn Fn(&Value, &Value) -> bool + Send + Sync>;
+
+/// How two values of one prop are judged equal
+#[derive(Clone)]
+pub enum PropComparator {
+    Exact,
+    /// Numbers within the tolerance are equal, also inside arrays and objects
+    Epsilon(f64),
+    /// Class lists as sets: whitespace-separated strings or arrays, in any order
+    Unordered,
+    /// Deep equality treating `null` object fields as absent
+    IgnoreNulls,
+    Custom(CompareFn),
+}
+
+impl std::fmt::Debug for PropComparator {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PropComparator::Exact => write!(f, "Exact"),
+            PropComparator::Epsilon(eps) => write!(f, "Epsilon({})", eps),
+            PropComparator::Unordered => write!(f, "Unordered"),
+            PropComparator::IgnoreNulls => write!(f, "IgnoreNulls"),
+            PropComparator::Custom(_) => write!(f, "Custom(<fn>)"),
+        }
+    }
+}
+
+impl PropComparator {
+    pub fn equal(&self, old: &Value, new: &Value) -> bool {
+        match self {
+            PropComparator::Exact => old == new,
+            PropComparator::Epsilon(eps) => approx_equal(old, new, *eps),
+            PropComparator::Unordered => unordered_equal(old, new),
+            PropComparator::IgnoreNulls => equal_ignoring_nulls(old, new),
+            PropComparator::Custom(compare) => compare(old, new),
+        }
+    }
+}
+
+fn approx_equal(old: &Value, new: &Value, eps: f64) -> bool {
+    match (old, new) {
+        (Value::Number(a), Value::Number(b)) => match (a.as_f64(), b.as_f64()) {
+            (Some(a), Some(b)) => (a - b).abs() <= eps,
+            _ => a == b,
+        },
+        (Value::Array(a), Value::Array(b)) => {
+            a.len() == b.len() && a.iter().zip(b).all(|(a, b)| approx_equal(a, b, eps))
+        }
+        (Value::Object(a), Value::Object(b)) => {
+            a.len() == b.len() && a.iter().all(|(k, a)| b.get(k).is_some_and(|b| approx_equal(a, b, eps)))
+        }
+        _ => old == new,
+    }
+}
+
+fn unordered_equal(old: &Value, new: &Value) -> bool {
+    fn items(value: &Value) -> Option<Vec<String>> {
+        let mut items: Vec<String> = match value {
+            Value::String(s) => s.split_whitespace().map(str::to_string).collect(),
+            Value::Array(a) => a.iter()
+                .map(|v| v.as_str().map_or_else(|| v.to_string(), str::to_string))
+                .collect(),
+            _ => return None,
+        };
+        items.sort_unstable();
+        items.dedup();
+        Some(items)
+    }
+    match (items(old), items(new)) {
+        (Some(a), Some(b)) => a == b,
+        _ => old == new,
+    }
+}
+
+fn equal_ignoring_nulls(old: &Value, new: &Value) -> bool {
+    match (old, new) {
+        (Value::Array(a), Value::Array(b)) => {
+            a.len() == b.len() && a.iter().zip(b).all(|(a, b)| equal_ignoring_nulls(a, b))
+        }
+        (Value::Object(a), Value::Object(b)) => {
+            let keys: HashSet<&String> = a.iter().chain(b.iter())
+                .filter(|(_, v)| !v.is_null())
+                .map(|(k, _)| k)
+                .collect();
+            keys.into_iter().all(|k| match (a.get(k), b.get(k)) {
+                (Some(a), Some(b)) => equal_ignoring_nulls(a, b),
+                _ => false,
+            })
+        }
+        _ => old == new,
+    }
+}
+
+/// Ignore lists and comparators for prop diffing
+#[derive(Debug, Clone)]
+pub struct PropDiffRules {
+    /// Ignored for every widget type
+    pub ignored: HashSet<String>,
+    /// Ignored in addition to `ignored` for one widget type
+    pub ignored_by_type: HashMap<String, HashSet<String>>,
+    pub comparators: HashMap<String, PropComparator>,
+    /// Take precedence over `comparators` for one widget type
+    pub comparators_by_type: HashMap<String, HashMap<String, PropComparator>>,
+}
+
+impl Default for PropDiffRules {
+    fn default() -> Self {
+        PropDiffRules {
+            ignored: DEFAULT_IGNORED_PROPS.iter().map(|p| p.to_string()).collect(),
+            ignored_by_type: HashMap::new(),
+            comparators: HashMap::new(),
+            comparators_by_type: HashMap::new(),
+        }
+    }
+}
+
+impl PropDiffRules {
+    pub fn is_ignored(&self, widget_type: &str, prop: &str) -> bool {
+        self.ignored.contains(prop)
+            || self.ignored_by_type.get(widget_type).is_some_and(|props| props.contains(prop))
+    }
+
+    pub fn comparator(&self, widget_type: &str, prop: &str) -> Option<&PropComparator> {
+        self.comparators_by_type.get(widget_type)
+            .and_then(|comparators| comparators.get(prop))
+            .or_else(|| self.comparators.get(prop))
+    }
+
+    /// Compare props of two renders of a `widget_type` node
+    pub fn diff(
+        &self,
+        widget_type: &str,
+        old: &HashMap<String, Value>,
+        new: &HashMap<String, Value>,
+    ) -> PropDiff {
+        let all_keys: HashSet<_> = old.keys().chain(new.keys())
+            .filter(|k| !self.is_ignored(widget_type, k))
+            .collect();
+
+        let mut diff = PropDiff::default();
+        for key in all_keys {
+            match (old.get(key), new.get(key)) {
+                (Some(old_val), Some(new_val)) => {
+                    let equal = match self.comparator(widget_type, key) {
+                        Some(comparator) => comparator.equal(old_val, new_val),
+                        None => old_val == new_val,
+                    };
+                    if !equal {
+                        diff.changed.insert(key.clone(), new_val.clone());
+                    }
+                }
+                (None, Some(new_val)) => {
+                    diff.changed.insert(key.clone(), new_val.clone());
+                }
+                (_, None) => diff.removed.push(key.clone()),
+            }
+        }
+        diff.removed.sort();
+        diff
+    }
+}
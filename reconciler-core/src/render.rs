@@ -0,0 +1,93 @@
+//! Whole-tree HTML serialization for the first paint.
+//!
+//! Each node's `html` is the stub of its own element. Rendering a tree
+//! places the children's markup just before the stub's outer closing tag;
+//! non-renderable nodes (see [`is_renderable_type`]) emit no element of
+//! their own and contribute their children in place.
+use crate::types::{NodeMap, is_renderable_type};
+use log::warn;
+
+/// Split a stub into the part before and including any existing inner
+/// content, and its outer closing tag. The closing part is empty when the
+/// stub is a void element (or otherwise has no matching close tag).
+pub fn split_stub(html: &str) -> (&str, &str) {
+    let trimmed = html.trim_end();
+    let tag: String = trimmed.trim_start()
+        .strip_prefix('<')
+        .unwrap_or("")
+        .chars()
+        .take_while(|c| c.is_ascii_alphanumeric() || *c == '-')
+        .collect();
+    if tag.is_empty() {
+        return (html, "");
+    }
+    let close = format!("</{}>", tag);
+    match trimmed.len().checked_sub(close.len()) {
+        // `at` may fall inside a multi-byte char when there is no close tag
+        Some(at) if trimmed.get(at..).is_some_and(|end| end.eq_ignore_ascii_case(&close)) => {
+            (&trimmed[..at], &trimmed[at..])
+        }
+        _ => (html, ""),
+    }
+}
+
+/// Complete nested HTML for the subtree rooted at `root_key`
+pub fn render_to_string<W>(map: &NodeMap<W>, root_key: &str) -> String {
+    RenderChunks::new(map, root_key).collect()
+}
+
+enum Step<'a> {
+    Node(&'a str),
+    Close(&'a str),
+}
+
+/// Document-order HTML chunks of a rendered tree: the opening part of each
+/// element (including any inner content of its stub), then its children,
+/// then its closing tag. Concatenated, the chunks equal [`render_to_string`].
+pub struct RenderChunks<'a, W = ()> {
+    map: &'a NodeMap<W>,
+    stack: Vec<Step<'a>>,
+}
+
+impl<'a, W> RenderChunks<'a, W> {
+    pub fn new(map: &'a NodeMap<W>, root_key: &'a str) -> Self {
+        RenderChunks { map, stack: vec![Step::Node(root_key)] }
+    }
+}
+
+impl<'a, W> Iterator for RenderChunks<'a, W> {
+    type Item = String;
+
+    fn next(&mut self) -> Option<String> {
+        while let Some(step) = self.stack.pop() {
+            let key = match step {
+                Step::Close(close) => return Some(close.to_string()),
+                Step::Node(key) => key,
+            };
+            let Some(node) = self.map.get(key) else {
+                warn!("render_to_string: key '{}' missing from rendered map", key);
+                continue;
+            };
+            let children = node.children_keys.iter().rev().map(|k| Step::Node(k.as_str()));
+            if !is_renderable_type(&node.widget_type) {
+                self.stack.extend(children);
+                continue;
+            }
+
+            let (open, close) = split_stub(&node.html);
+            if close.is_empty() {
+                if !node.children_keys.is_empty() {
+                    warn!(
+                        "render_to_string: {} '{}' has no closing tag, dropping {} children",
+                        node.widget_type, key, node.children_keys.len()
+                    );
+                }
+            } else {
+                self.stack.push(Step::Close(close));
+                self.stack.extend(children);
+            }
+            return Some(open.to_string());
+        }
+        None
+    }
+}
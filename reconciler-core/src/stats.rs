@@ -0,0 +1,32 @@
+//! Optional per-reconciliation statistics
+use crate::types::{PatchAction, RustPatch};
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// What one reconciliation did, collected when `DiffOptions::collect_stats`
+/// is set
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ReconcileStats {
+    pub old_node_count: usize,
+    pub new_node_count: usize,
+    /// Number of emitted patches per action
+    pub patch_counts: HashMap<PatchAction, usize>,
+    /// LIS length of every `diff_children` call that had children to place
+    pub lis_lengths: Vec<usize>,
+    /// Subtrees carried over without prop diffs or patches, because they
+    /// were memoized or their subtree hash matched the old one
+    pub kept_subtrees: usize,
+    /// Wall time spent in `DiffEngine::reconcile` and the removal pass
+    pub diff_duration: Duration,
+    /// Wall time spent running lifecycle hooks after the diff
+    pub lifecycle_duration: Duration,
+}
+
+impl ReconcileStats {
+    pub fn count_patches(&mut self, patches: &[RustPatch]) {
+        self.patch_counts.clear();
+        for patch in patches {
+            *self.patch_counts.entry(patch.action.clone()).or_insert(0) += 1;
+        }
+    }
+}
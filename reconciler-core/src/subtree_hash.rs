@@ -0,0 +1,121 @@
+//! Merkle-style content hashes over rendered subtrees
+//!
+//! A node's hash covers its widget type, key, props (object keys in sorted
+//! order, so insertion order never matters) and the ordered hashes of its
+//! children. html_ids are left out, so equal content hashes equally wherever
+//! it renders.
+use crate::types::NodeMap;
+use serde_json::Value;
+use std::hash::Hasher;
+
+/// 64-bit FNV-1a: stable across Rust versions and processes, unlike
+/// `DefaultHasher`
+pub(crate) struct Fnv1a(u64);
+
+impl Default for Fnv1a {
+    fn default() -> Self {
+        Fnv1a(0xcbf2_9ce4_8422_2325)
+    }
+}
+
+impl Hasher for Fnv1a {
+    fn write(&mut self, bytes: &[u8]) {
+        for b in bytes {
+            self.0 ^= u64::from(*b);
+            self.0 = self.0.wrapping_mul(0x0000_0100_0000_01b3);
+        }
+    }
+
+    fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+/// Strings are length-prefixed so adjacent fields cannot run together
+pub(crate) fn write_str(h: &mut Fnv1a, s: &str) {
+    h.write_u64(s.len() as u64);
+    h.write(s.as_bytes());
+}
+
+fn write_value(h: &mut Fnv1a, value: &Value) {
+    match value {
+        Value::Null => h.write_u8(0),
+        Value::Bool(b) => {
+            h.write_u8(1);
+            h.write_u8(u8::from(*b));
+        }
+        Value::Number(n) => {
+            h.write_u8(2);
+            write_str(h, &n.to_string());
+        }
+        Value::String(s) => {
+            h.write_u8(3);
+            write_str(h, s);
+        }
+        Value::Array(items) => {
+            h.write_u8(4);
+            h.write_u64(items.len() as u64);
+            for item in items {
+                write_value(h, item);
+            }
+        }
+        Value::Object(map) => {
+            h.write_u8(5);
+            h.write_u64(map.len() as u64);
+            let mut entries: Vec<_> = map.iter().collect();
+            entries.sort_unstable_by_key(|(k, _)| *k);
+            for (k, v) in entries {
+                write_str(h, k);
+                write_value(h, v);
+            }
+        }
+    }
+}
+
+/// Fill `subtree_hash` for every node under the roots of `map` (nodes
+/// without a parent in it), children before parents. Memoized nodes that
+/// already carry a hash keep it without their subtree being visited.
+pub fn compute_subtree_hashes<W>(map: &mut NodeMap<W>) {
+    // (key, children already pushed)
+    let mut stack: Vec<(String, bool)> = map.values()
+        .filter(|node| node.parent_key.as_ref().is_none_or(|parent| !map.contains_key(parent)))
+        .map(|node| (node.key.clone(), false))
+        .collect();
+    while let Some((key, expanded)) = stack.pop() {
+        let Some(node) = map.get(&key) else {
+            continue;
+        };
+        if node.memoized && node.subtree_hash.is_some() {
+            continue;
+        }
+        if !expanded {
+            stack.push((key.clone(), true));
+            // Each node is on the stack at most once in a tree, so a longer
+            // stack means `children_keys` loops back and expanding would never end
+            if stack.len() <= map.len() {
+                stack.extend(node.children_keys.iter().map(|child| (child.clone(), false)));
+            }
+            continue;
+        }
+
+        let mut h = Fnv1a::default();
+        write_str(&mut h, &node.widget_type);
+        write_str(&mut h, &node.key);
+        let mut props: Vec<_> = node.props.iter().collect();
+        props.sort_unstable_by_key(|(k, _)| *k);
+        h.write_u64(props.len() as u64);
+        for (k, v) in props {
+            write_str(&mut h, k);
+            write_value(&mut h, v);
+        }
+        h.write_u64(node.children_keys.len() as u64);
+        for child in &node.children_keys {
+            // A dangling child key still changes the hash
+            h.write_u64(map.get(child).and_then(|c| c.subtree_hash).unwrap_or(0));
+        }
+        let hash = h.finish();
+        if let Some(node) = map.get_mut(&key) {
+            node.subtree_hash = Some(hash);
+        }
+    }
+}
@@ -0,0 +1,152 @@
+//! Plain Rust node and patch types shared by every host
+use crate::errors::ReconcilerError;
+use crate::host::LifecycleError;
+use crate::stats::ReconcileStats;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+
+/// JS initializer with sanitized data
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct JsInitializer {
+    #[serde(rename = "type")]
+    pub init_type: String,
+    pub target_id: String,
+    pub data: serde_json::Value,
+    pub before_id: Option<String>,
+}
+
+/// Patch action enum
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum PatchAction {
+    Insert,
+    Remove,
+    Update,
+    Move,
+    Replace,
+}
+
+impl fmt::Display for PatchAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            PatchAction::Insert => "INSERT",
+            PatchAction::Remove => "REMOVE",
+            PatchAction::Update => "UPDATE",
+            PatchAction::Move => "MOVE",
+            PatchAction::Replace => "REPLACE",
+        };
+        f.write_str(s)
+    }
+}
+
+/// Native patch representation (zero-GIL processing)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RustPatch {
+    pub action: PatchAction,
+    pub html_id: String,
+    pub data: serde_json::Value,
+}
+
+/// Node data for one widget in a rendered tree.
+///
+/// `W` is the host's handle to the originating widget object (a Python
+/// object for the PyO3 bindings, `()` for pure Rust callers).
+#[derive(Debug, Clone)]
+pub struct RustNodeData<W = ()> {
+    pub html_id: String,
+    pub html: String,
+    pub widget_type: String,
+    pub key: String,
+    pub widget_instance: Option<W>,
+    pub props: HashMap<String, serde_json::Value>,
+    pub parent_html_id: String,
+    pub parent_key: Option<String>,
+    pub children_keys: Vec<String>,
+    /// Host-supplied hash/version of the widget; equal values across
+    /// renders mean the subtree is unchanged
+    pub memo_key: Option<String>,
+    /// Set by the tree builder on the root of a subtree it copied unchanged
+    /// from the previous tree; the diff keeps that subtree without patches
+    pub memoized: bool,
+    /// Merkle hash of this subtree (see `subtree_hash`); None until computed
+    pub subtree_hash: Option<u64>,
+}
+
+impl<W> RustNodeData<W> {
+    /// Safely extract a property with detailed error
+    pub fn get_prop(&self, key: &str) -> Result<&serde_json::Value, ReconcilerError> {
+        self.props.get(key).ok_or_else(|| ReconcilerError::PropError {
+            property: key.to_string(),
+            details: "Property not found".to_string(),
+        })
+    }
+}
+
+/// Rendered tree keyed by widget key
+pub type NodeMap<W = ()> = HashMap<String, RustNodeData<W>>;
+
+/// Complete reconciliation result in native types
+#[derive(Debug)]
+pub struct RustReconciliationResult<W = ()> {
+    pub patches: Vec<RustPatch>,
+    pub new_rendered_map: NodeMap<W>,
+    pub js_initializers: Vec<JsInitializer>,
+    /// Present when the diff ran with `DiffOptions::collect_stats`
+    pub stats: Option<ReconcileStats>,
+    /// Hooks that failed, in dispatch order
+    pub lifecycle_errors: Vec<LifecycleError>,
+}
+
+/// Browser-bound wire payload: `{"patches": [...], "js_initializers": [...]}`
+/// where each patch is `{"action": "INSERT", "html_id": "...", "data": {...}}`
+#[derive(Debug, Serialize, Deserialize, PartialEq)]
+pub struct PatchPayload {
+    pub patches: Vec<RustPatch>,
+    pub js_initializers: Vec<JsInitializer>,
+}
+
+/// Serialize patches and initializers into one UTF-8 JSON buffer
+pub fn patches_to_json_bytes(
+    patches: &[RustPatch],
+    js_initializers: &[JsInitializer],
+) -> Result<Vec<u8>, ReconcilerError> {
+    #[derive(Serialize)]
+    struct Borrowed<'a> {
+        patches: &'a [RustPatch],
+        js_initializers: &'a [JsInitializer],
+    }
+    Ok(serde_json::to_vec(&Borrowed { patches, js_initializers })?)
+}
+
+impl<W> RustReconciliationResult<W> {
+    /// Wire payload ready for a WebSocket/QWebChannel send
+    pub fn to_json_bytes(&self) -> Result<Vec<u8>, ReconcilerError> {
+        patches_to_json_bytes(&self.patches, &self.js_initializers)
+    }
+
+    /// MessagePack form of the wire payload, see [`crate::msgpack`]
+    #[cfg(feature = "msgpack")]
+    pub fn to_msgpack_bytes(&self) -> Vec<u8> {
+        crate::msgpack::encode_payload(&self.patches, &self.js_initializers)
+    }
+}
+
+impl<W> Default for RustReconciliationResult<W> {
+    fn default() -> Self {
+        RustReconciliationResult {
+            patches: Vec::new(),
+            new_rendered_map: HashMap::new(),
+            js_initializers: Vec::new(),
+            stats: None,
+            lifecycle_errors: Vec::new(),
+        }
+    }
+}
+
+/// Return true when widget_type corresponds to a real DOM-rendered element.
+/// Internal wrapper/proxy types are non-renderable so their children attach
+/// to the nearest real ancestor.
+pub fn is_renderable_type(widget_type: &str) -> bool {
+    !(widget_type == "StatefulWidget" || widget_type == "StatelessWidget" || widget_type == "_WidgetProxy")
+}
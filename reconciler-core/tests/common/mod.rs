@@ -0,0 +1,119 @@
+//! Prebuilt node maps shared by the integration tests
+#![allow(dead_code)]
+use reconciler_core::{
+    DiffHost, DiffOptions, NodeMap, NullHost, ReconcilerError, RustNodeData, RustPatch,
+    RustReconciliationResult, is_renderable_type, reconcile,
+};
+use std::collections::HashMap;
+
+/// Build a map from (key, widget_type, parent key) rows in child order.
+/// Every node's html_id is `id_<key>`.
+pub fn tree(rows: &[(&str, &str, Option<&str>)]) -> NodeMap {
+    let mut map = NodeMap::new();
+    for &(key, widget_type, parent) in rows {
+        let parent_html_id = parent
+            .and_then(|p| map.get(p))
+            .map(|p: &RustNodeData| if is_renderable_type(&p.widget_type) { p.html_id.clone() } else { p.parent_html_id.clone() })
+            .unwrap_or_else(|| "body".to_string());
+        if let Some(p) = parent.and_then(|p| map.get_mut(p)) {
+            p.children_keys.push(key.to_string());
+        }
+        map.insert(key.to_string(), RustNodeData {
+            html_id: format!("id_{}", key),
+            html: format!("<div id=\"id_{}\"></div>", key),
+            widget_type: widget_type.to_string(),
+            key: key.to_string(),
+            widget_instance: None,
+            props: HashMap::new(),
+            parent_html_id,
+            parent_key: parent.map(String::from),
+            children_keys: Vec::new(),
+            memo_key: None,
+            memoized: false,
+            subtree_hash: None,
+        });
+    }
+    map
+}
+
+/// Set one prop on a node of `map`
+pub fn set_prop(map: &mut NodeMap, key: &str, prop: &str, value: serde_json::Value) {
+    map.get_mut(key).expect("node in map").props.insert(prop.to_string(), value);
+}
+
+/// Full reconciliation from the node keyed "root" with `NullHost`
+pub fn diff(old: &NodeMap, new: &NodeMap, options: DiffOptions) -> RustReconciliationResult {
+    reconcile(&mut NullHost, old, new, "body", false, Some("root".to_string()), options)
+        .unwrap_or_else(|_| panic!("reconcile failed"))
+}
+
+/// (action, html_id) of every patch, in order
+pub fn summary(patches: &[RustPatch]) -> Vec<(String, &str)> {
+    patches.iter().map(|p| (p.action.to_string(), p.html_id.as_str())).collect()
+}
+
+/// Host that records every callback as (method, node key) and fails the
+/// calls listed in `fail`
+#[derive(Debug, Default)]
+pub struct Recorder {
+    pub calls: Vec<(&'static str, String)>,
+    pub fail: Vec<(&'static str, String)>,
+}
+
+impl Recorder {
+    fn record(&mut self, method: &'static str, node: &RustNodeData) -> Result<(), ReconcilerError> {
+        self.calls.push((method, node.key.clone()));
+        if self.fail.iter().any(|(m, k)| *m == method && *k == node.key) {
+            return Err(ReconcilerError::KeyError { details: format!("{} failed", method) });
+        }
+        Ok(())
+    }
+
+    /// Keys passed to `method`, in call order
+    pub fn keys(&self, method: &str) -> Vec<&str> {
+        self.calls.iter().filter(|(m, _)| *m == method).map(|(_, k)| k.as_str()).collect()
+    }
+
+    /// Full reconciliation from the node keyed "root"
+    pub fn diff(&mut self, old: &NodeMap, new: &NodeMap, options: DiffOptions) -> RustReconciliationResult {
+        reconcile(self, old, new, "body", false, Some("root".to_string()), options)
+            .unwrap_or_else(|e| panic!("reconcile failed: {}", e))
+    }
+}
+
+impl DiffHost for Recorder {
+    type Widget = ();
+    type Error = ReconcilerError;
+
+    fn collect_details(&mut self, node: &RustNodeData) -> Result<(), ReconcilerError> {
+        self.record("collect_details", node)
+    }
+
+    fn reuse_details(&mut self, node: &RustNodeData) -> Result<(), ReconcilerError> {
+        self.record("reuse_details", node)
+    }
+
+    fn init_state(&mut self, node: &RustNodeData) -> Result<(), ReconcilerError> {
+        self.record("init_state", node)
+    }
+
+    fn did_change_dependencies(&mut self, node: &RustNodeData) -> Result<(), ReconcilerError> {
+        self.record("did_change_dependencies", node)
+    }
+
+    fn deactivate(&mut self, node: &RustNodeData) -> Result<(), ReconcilerError> {
+        self.record("deactivate", node)
+    }
+
+    fn activate(&mut self, node: &RustNodeData) -> Result<(), ReconcilerError> {
+        self.record("activate", node)
+    }
+
+    fn did_update_widget(&mut self, _old: &RustNodeData, new: &RustNodeData) -> Result<(), ReconcilerError> {
+        self.record("did_update_widget", new)
+    }
+
+    fn dispose(&mut self, node: &RustNodeData) -> Result<(), ReconcilerError> {
+        self.record("dispose", node)
+    }
+}
@@ -0,0 +1,156 @@
+mod common;
+
+use common::{diff, set_prop, summary, tree};
+use reconciler_core::{
+    DiffOptions, PatchAction, RustPatch, diff_props, longest_increasing_subsequence,
+    removes_first, reorder_patches_parent_first,
+};
+use serde_json::json;
+use std::collections::HashMap;
+
+/// `lis` must index a strictly increasing run of `seq`
+fn assert_increasing(seq: &[usize], lis: &[usize]) {
+    assert!(lis.windows(2).all(|w| w[0] < w[1] && seq[w[0]] < seq[w[1]]), "{:?} in {:?}", lis, seq);
+}
+
+#[test]
+fn lis_of_empty_and_sorted_sequences() {
+    assert!(longest_increasing_subsequence(&[]).is_empty());
+    assert_eq!(longest_increasing_subsequence(&[0, 1, 2, 3]), [0, 1, 2, 3]);
+    assert_eq!(longest_increasing_subsequence(&[3, 2, 1, 0]).len(), 1);
+}
+
+#[test]
+fn lis_finds_a_longest_run() {
+    let seq = [2, 0, 3, 1, 4, 6, 5];
+    let lis = longest_increasing_subsequence(&seq);
+    assert_increasing(&seq, &lis);
+    assert_eq!(lis.len(), 4);
+
+    let seq = [4, 0, 1, 2, 3];
+    assert_eq!(longest_increasing_subsequence(&seq), [1, 2, 3, 4]);
+}
+
+#[test]
+fn moving_one_child_emits_a_single_move() {
+    let old = tree(&[
+        ("root", "Column", None),
+        ("a", "Text", Some("root")),
+        ("b", "Text", Some("root")),
+        ("c", "Text", Some("root")),
+    ]);
+    let new = tree(&[
+        ("root", "Column", None),
+        ("c", "Text", Some("root")),
+        ("a", "Text", Some("root")),
+        ("b", "Text", Some("root")),
+    ]);
+
+    let result = diff(&old, &new, DiffOptions::default());
+    assert_eq!(summary(&result.patches), [("MOVE".to_string(), "id_c")]);
+    assert_eq!(result.patches[0].data, json!({ "parent_html_id": "id_root", "before_id": "id_a" }));
+}
+
+#[test]
+fn inserted_child_goes_before_its_next_sibling() {
+    let old = tree(&[
+        ("root", "Column", None),
+        ("a", "Text", Some("root")),
+        ("b", "Text", Some("root")),
+    ]);
+    let new = tree(&[
+        ("root", "Column", None),
+        ("a", "Text", Some("root")),
+        ("x", "Text", Some("root")),
+        ("b", "Text", Some("root")),
+    ]);
+
+    let result = diff(&old, &new, DiffOptions::default());
+    assert_eq!(summary(&result.patches), [("INSERT".to_string(), "id_x")]);
+    let data = &result.patches[0].data;
+    assert_eq!(data["parent_html_id"], "id_root");
+    assert_eq!(data["before_id"], "id_b");
+    assert_eq!(data["html"], "<div id=\"id_x\"></div>");
+    assert!(result.new_rendered_map.contains_key("x"));
+}
+
+#[test]
+fn inserted_subtree_attaches_to_its_new_parent() {
+    let old = tree(&[("root", "Column", None)]);
+    let new = tree(&[
+        ("root", "Column", None),
+        ("a", "Column", Some("root")),
+        ("a1", "Text", Some("a")),
+    ]);
+
+    let result = diff(&old, &new, DiffOptions::default());
+    assert_eq!(summary(&result.patches), [("INSERT".to_string(), "id_a"), ("INSERT".to_string(), "id_a1")]);
+    assert_eq!(result.patches[1].data["parent_html_id"], "id_a");
+}
+
+#[test]
+fn changed_props_produce_an_update() {
+    let mut old = tree(&[("root", "Column", None), ("a", "Text", Some("root"))]);
+    let mut new = old.clone();
+    set_prop(&mut old, "a", "data", json!("old"));
+    set_prop(&mut old, "a", "color", json!("red"));
+    set_prop(&mut new, "a", "data", json!("new"));
+
+    let result = diff(&old, &new, DiffOptions::default());
+    assert_eq!(summary(&result.patches), [("UPDATE".to_string(), "id_a")]);
+    assert_eq!(result.patches[0].data, json!({ "props": { "data": "new" }, "removed_props": ["color"] }));
+}
+
+#[test]
+fn diff_props_reports_changed_and_removed_keys() {
+    let old: HashMap<_, _> = [("a", json!(1)), ("b", json!(2)), ("z", json!(0)), ("onPressed", json!("f"))]
+        .into_iter().map(|(k, v)| (k.to_string(), v)).collect();
+    let new: HashMap<_, _> = [("a", json!(1)), ("b", json!(3)), ("c", json!(4)), ("onPressed", json!("g"))]
+        .into_iter().map(|(k, v)| (k.to_string(), v)).collect();
+
+    let delta = diff_props(&old, &new);
+    assert_eq!(delta.changed, HashMap::from([("b".to_string(), json!(3)), ("c".to_string(), json!(4))]));
+    assert_eq!(delta.removed, ["z"]);
+    assert!(diff_props(&new, &new).is_empty());
+}
+
+fn insert(id: &str, parent: &str) -> RustPatch {
+    RustPatch { action: PatchAction::Insert, html_id: id.into(), data: json!({ "parent_html_id": parent }) }
+}
+
+fn update(id: &str) -> RustPatch {
+    RustPatch { action: PatchAction::Update, html_id: id.into(), data: json!({}) }
+}
+
+#[test]
+fn reorder_puts_parent_inserts_first() {
+    let mut patches = vec![insert("c", "b"), insert("b", "a"), update("u"), insert("a", "root"), insert("x", "root")];
+    reorder_patches_parent_first(&mut patches);
+
+    let order: Vec<&str> = patches.iter().map(|p| p.html_id.as_str()).collect();
+    let pos = |id| order.iter().position(|o| *o == id).unwrap();
+    assert!(pos("a") < pos("b") && pos("b") < pos("c"), "{:?}", order);
+    assert_eq!(patches.len(), 5);
+}
+
+#[test]
+fn reorder_keeps_independent_patches_in_order() {
+    let mut patches = vec![update("u1"), insert("a", "root"), update("u2"), insert("b", "root")];
+    let expected = patches.clone();
+    reorder_patches_parent_first(&mut patches);
+    assert_eq!(patches, expected);
+}
+
+#[test]
+fn removes_come_before_every_other_patch() {
+    let remove = |id: &str| RustPatch { action: PatchAction::Remove, html_id: id.into(), data: json!(null) };
+    let mut patches = vec![insert("x", "a"), update("u"), remove("x"), insert("y", "x"), remove("z")];
+    removes_first(&mut patches);
+    assert_eq!(summary(&patches), [
+        ("REMOVE".to_string(), "x"),
+        ("REMOVE".to_string(), "z"),
+        ("INSERT".to_string(), "x"),
+        ("UPDATE".to_string(), "u"),
+        ("INSERT".to_string(), "y"),
+    ]);
+}
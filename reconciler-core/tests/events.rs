@@ -0,0 +1,117 @@
+use reconciler_core::events::{DomEvent, EventBinding, EventMode, dom_events, event_bindings, push_event_attrs};
+use reconciler_core::html::parse_single_element;
+use serde_json::{Value, json};
+use std::collections::HashMap;
+
+fn props(pairs: &[(&str, Value)]) -> HashMap<String, Value> {
+    pairs.iter().map(|(k, v)| (k.to_string(), v.clone())).collect()
+}
+
+/// (event, key, callback) per binding
+fn summary(bindings: &[EventBinding]) -> Vec<(&str, Option<&str>, &str)> {
+    bindings.iter().map(|b| (b.event.as_str(), b.key.as_deref(), b.callback.as_str())).collect()
+}
+
+#[test]
+fn on_changed_depends_on_the_element() {
+    let input = [DomEvent { event: "input", key: None }];
+    assert_eq!(dom_events("onChanged", "input"), input);
+    assert_eq!(dom_events("onChanged", "textarea"), input);
+    assert_eq!(dom_events("onChanged", "select"), [DomEvent { event: "change", key: None }]);
+    assert!(dom_events("onLongPress", "div").is_empty());
+}
+
+#[test]
+fn bindings_are_ordered_by_prop_name() {
+    let bindings = event_bindings("fw_id_1", "div", &props(&[
+        ("onHoverName", json!("hover")),
+        ("onDragName", json!("drag")),
+        ("onTapName", json!("tap")),
+        ("onUnknownName", json!("nothing")),
+        ("styleName", json!("ignored")),
+        ("data", json!("text")),
+    ]));
+    assert_eq!(summary(&bindings), [
+        ("pointerdown", None, "drag"),
+        ("pointermove", None, "drag"),
+        ("pointerup", None, "drag"),
+        ("mouseenter", None, "hover"),
+        ("click", None, "tap"),
+    ]);
+    assert!(bindings.iter().all(|b| b.html_id == "fw_id_1"));
+}
+
+#[test]
+fn first_callback_by_name_keeps_a_shared_event() {
+    let bindings = event_bindings("x", "button", &props(&[
+        ("onTapName", json!("tap")),
+        ("onPressedName", json!("pressed")),
+    ]));
+    assert_eq!(summary(&bindings), [("click", None, "pressed")]);
+}
+
+#[test]
+fn disabled_widgets_bind_nothing() {
+    let mut p = props(&[("onPressedName", json!("pressed")), ("enabled", json!(false))]);
+    assert!(event_bindings("x", "button", &p).is_empty());
+    p.insert("enabled".to_string(), json!(true));
+    assert_eq!(event_bindings("x", "button", &p).len(), 1);
+    p.insert("enabled".to_string(), json!("no"));
+    assert_eq!(event_bindings("x", "button", &p).len(), 1);
+}
+
+#[test]
+fn args_and_keys_are_carried() {
+    let bindings = event_bindings("x", "input", &props(&[
+        ("onSubmittedName", json!("submit")),
+        ("onSubmittedArgs", json!([1, "a"])),
+        ("onChangedName", json!("changed")),
+        ("onChangedArgs", json!([])),
+        ("onTapName", json!(7)),
+    ]));
+    assert_eq!(summary(&bindings), [("input", None, "changed"), ("keydown", Some("Enter"), "submit")]);
+    assert_eq!(bindings[0].args, None);
+    assert_eq!(bindings[1].args, Some(json!([1, "a"])));
+}
+
+fn binding(event: &str, key: Option<&str>, callback: &str, args: Option<Value>) -> EventBinding {
+    EventBinding {
+        html_id: "x".to_string(),
+        event: event.to_string(),
+        key: key.map(str::to_string),
+        callback: callback.to_string(),
+        args,
+    }
+}
+
+/// Attribute values of `<b{attrs}>` as the browser sees them
+fn attrs_of(binding: &EventBinding, mode: EventMode) -> Vec<(String, String)> {
+    let mut attrs = String::new();
+    push_event_attrs(&mut attrs, binding, mode);
+    parse_single_element(&format!("<b{}></b>", attrs)).unwrap().tag.attrs
+}
+
+#[test]
+fn inline_handlers_keep_quotes_inside_their_js_strings() {
+    let click = binding("click", None, "cb", Some(json!(["O'Brien", "\"q\"", "</script>"])));
+    assert_eq!(attrs_of(&click, EventMode::Inline), [(
+        "onclick".to_string(),
+        r#"handleClickWithArgs('cb', '[\"O\'Brien\",\"\\\"q\\\"\",\"\x3C/script>\"]')"#.to_string(),
+    )]);
+
+    let crafted = binding("keydown", Some("'"), "a');alert(1);('", None);
+    assert_eq!(attrs_of(&crafted, EventMode::Inline), [(
+        "onkeydown".to_string(),
+        r#"if (event.key === '\'') handleEvent(event, 'a\');alert(1);(\'', '[]')"#.to_string(),
+    )]);
+}
+
+#[test]
+fn data_attributes_carry_raw_values() {
+    let submit = binding("keydown", Some("Enter"), "it's", Some(json!(["O'Brien"])));
+    assert_eq!(attrs_of(&submit, EventMode::DataAttributes), [
+        ("data-fw-on-keydown".to_string(), "it's".to_string()),
+        ("data-fw-args-keydown".to_string(), r#"["O'Brien"]"#.to_string()),
+        ("data-fw-key-keydown".to_string(), "Enter".to_string()),
+    ]);
+}
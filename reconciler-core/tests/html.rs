@@ -0,0 +1,93 @@
+use reconciler_core::ReconcilerError;
+use reconciler_core::html::{Element, HtmlNode, parse_fragment, parse_single_element, scan_start_tags};
+
+#[test]
+fn stray_equals_before_multibyte_chars_does_not_split_them() {
+    let tags = scan_start_tags(r#"<a ="x"é>hi</a>"#);
+    assert_eq!(tags[0].name, "a");
+    assert_eq!(tags[0].attrs, [("é".to_string(), String::new())]);
+
+    let el = parse_single_element("<div ='a'ü id='fw_id_1'></div>").unwrap();
+    assert_eq!(el.attr("id"), Some("fw_id_1"));
+    assert_eq!(el.attr("ü"), Some(""));
+
+    for html in ["<p =é>", "<p = ü>x</p>", "<p ==>", "<p =\"é\"=ü>"] {
+        let _ = parse_fragment(html);
+    }
+}
+
+fn element(node: &HtmlNode) -> &Element {
+    match node {
+        HtmlNode::Element(e) => e,
+        HtmlNode::Text(t) => panic!("expected an element, got text {:?}", t),
+    }
+}
+
+fn parse_error(html: &str) -> (usize, String) {
+    match parse_fragment(html) {
+        Err(ReconcilerError::HtmlParseError { position, details }) => (position, details),
+        other => panic!("expected a parse error for {:?}, got {:?}", html, other),
+    }
+}
+
+#[test]
+fn fragment_keeps_structure_attributes_and_text() {
+    let html = concat!(
+        r#"<div id="fw_id_0" class="a  b" style="color: red; content: 'x;y'; ">"#,
+        r#"<p id="fw_id_1" title="&quot;hi&quot; &amp; &#233;">a &lt; b</p>"#,
+        r#"<!-- skipped --><img src="x.png" alt><br/>"#,
+        r#"<SCRIPT>if (a < b) {}</SCRIPT>"#,
+        "</div>tail",
+    );
+    let nodes = parse_fragment(html).unwrap();
+    assert_eq!(nodes.len(), 2);
+    assert_eq!(nodes[1], HtmlNode::Text("tail".to_string()));
+
+    let div = element(&nodes[0]);
+    assert_eq!(div.tag.name, "div");
+    assert_eq!(div.classes(), ["a", "b"]);
+    assert_eq!(div.style(), [("color", "red"), ("content", "'x;y'")]);
+    let tags: Vec<&str> = div.children.iter().map(|c| element(c).tag.name.as_str()).collect();
+    assert_eq!(tags, ["p", "img", "br", "script"]);
+
+    let p = element(&div.children[0]);
+    assert_eq!(p.attr("title"), Some("\"hi\" & é"));
+    assert_eq!(p.text(), "a < b");
+    assert_eq!(element(&div.children[1]).tag.attrs, [
+        ("src".to_string(), "x.png".to_string()),
+        ("alt".to_string(), String::new()),
+    ]);
+    assert_eq!(element(&div.children[3]).text(), "if (a < b) {}");
+}
+
+#[test]
+fn parsed_stub_matches_scanned_start_tags() {
+    let html = r#"<ul id="l"><li id="i1">one</li><li id="i2"><input id="in" value="v"></li></ul>"#;
+    let el = parse_single_element(html).unwrap();
+    let mut from_tree = Vec::new();
+    let mut stack = vec![&el];
+    while let Some(e) = stack.pop() {
+        from_tree.push(e.tag.clone());
+        stack.extend(e.children.iter().rev().filter_map(|c| match c {
+            HtmlNode::Element(e) => Some(e),
+            HtmlNode::Text(_) => None,
+        }));
+    }
+    assert_eq!(from_tree, scan_start_tags(html));
+}
+
+#[test]
+fn errors_point_at_the_offending_tag() {
+    assert_eq!(parse_error("<div><p>x</div>"), (9, "</div> closes <p>".to_string()));
+    assert_eq!(parse_error("<div>ok</div><span>x"), (13, "<span> is never closed".to_string()));
+    assert_eq!(parse_error("ab</p>"), (2, "</p> without an open element".to_string()));
+    assert_eq!(parse_fragment("<br></br><hr/>").unwrap().len(), 2);
+}
+
+#[test]
+fn single_element_rejects_stray_text_and_siblings() {
+    assert!(parse_single_element("  <p>x</p>\n").is_ok());
+    assert!(parse_single_element("<p>x</p>y").is_err());
+    assert!(parse_single_element("<p></p><p></p>").is_err());
+    assert!(parse_single_element("").is_err());
+}
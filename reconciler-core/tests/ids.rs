@@ -0,0 +1,60 @@
+use reconciler_core::{IdGenerator, IdStrategy, is_valid_id_prefix};
+
+fn path(keys: &[&str]) -> Vec<String> {
+    keys.iter().map(|k| k.to_string()).collect()
+}
+
+#[test]
+fn counter_restarts_after_reset() {
+    let ids = IdGenerator::new(IdStrategy::Counter, "w_");
+    let first: Vec<String> = (0..3).map(|_| ids.next_id(Vec::new)).collect();
+    assert_eq!(first, ["w_0", "w_1", "w_2"]);
+    ids.reset();
+    assert_eq!(ids.next_id(Vec::new), "w_0");
+}
+
+#[test]
+fn counters_are_per_generator() {
+    let a = IdGenerator::new(IdStrategy::Counter, "a_");
+    let b = IdGenerator::new(IdStrategy::Counter, "b_");
+    a.next_id(Vec::new);
+    a.next_id(Vec::new);
+    assert_eq!(b.next_id(Vec::new), "b_0");
+}
+
+#[test]
+fn key_path_ids_are_deterministic() {
+    let a = IdGenerator::new(IdStrategy::KeyPath, "fw_id_");
+    let b = IdGenerator::new(IdStrategy::KeyPath, "fw_id_");
+    let id = a.next_id(|| path(&["root", "list", "item"]));
+    assert_eq!(id, a.next_id(|| path(&["root", "list", "item"])));
+    assert_eq!(id, b.next_id(|| path(&["root", "list", "item"])));
+    assert_ne!(id, a.next_id(|| path(&["root", "list", "other"])));
+    assert!(a.is_generated(&id));
+}
+
+#[test]
+fn key_path_segments_cannot_run_together() {
+    let ids = IdGenerator::new(IdStrategy::KeyPath, "fw_id_");
+    // A disambiguated key contains '/'
+    assert_ne!(ids.next_id(|| path(&["r", "a", "b#0"])), ids.next_id(|| path(&["r", "a/b#0"])));
+    assert_ne!(ids.next_id(|| path(&["ab", "c"])), ids.next_id(|| path(&["a", "bc"])));
+}
+
+#[test]
+fn observed_ids_are_not_minted_again() {
+    let ids = IdGenerator::new(IdStrategy::Counter, "fw_id_");
+    ids.observe("fw_id_7");
+    ids.observe("other_9");
+    assert_eq!(ids.next_id(Vec::new), "fw_id_8");
+}
+
+#[test]
+fn id_prefixes_must_be_safe_in_markup() {
+    for prefix in ["fw_id_", "app-", "x"] {
+        assert!(is_valid_id_prefix(prefix), "{}", prefix);
+    }
+    for prefix in ["", "1x", "a\"b", "a>b", "a b", "a/b", "é"] {
+        assert!(!is_valid_id_prefix(prefix), "{}", prefix);
+    }
+}
@@ -0,0 +1,54 @@
+mod common;
+
+use common::tree;
+use reconciler_core::{ancestor_keys, disambiguated_key, key_path, parent_chain};
+use std::collections::HashMap;
+
+#[test]
+fn key_path_runs_from_the_root() {
+    let map = tree(&[
+        ("root", "Column", None),
+        ("a", "Column", Some("root")),
+        ("a1", "Text", Some("a")),
+    ]);
+    assert_eq!(key_path(&map, Some("a1")), ["root", "a", "a1"]);
+    assert_eq!(key_path(&map, Some("root")), ["root"]);
+    assert!(key_path(&map, None).is_empty());
+}
+
+#[test]
+fn key_path_of_a_key_not_yet_in_the_map_is_just_that_key() {
+    let map = tree(&[("root", "Column", None)]);
+    assert_eq!(key_path(&map, Some("new")), ["new"]);
+}
+
+#[test]
+fn key_path_stops_on_a_parent_cycle() {
+    let mut map = tree(&[("a", "Column", None), ("b", "Column", Some("a"))]);
+    map.get_mut("a").unwrap().parent_key = Some("b".to_string());
+    assert!(key_path(&map, Some("b")).len() <= map.len() + 1);
+}
+
+#[test]
+fn disambiguated_keys_name_parent_and_position() {
+    assert_eq!(disambiguated_key("list", "item", 3), "list/item#3");
+    assert_ne!(disambiguated_key("list", "item", 3), disambiguated_key("list", "item", 4));
+}
+
+#[test]
+fn ancestor_keys_start_at_the_key() {
+    let map = tree(&[
+        ("root", "Column", None),
+        ("a", "Column", Some("root")),
+        ("a1", "Text", Some("a")),
+    ]);
+    assert_eq!(ancestor_keys(&map, Some("a1")).collect::<Vec<_>>(), ["a1", "a", "root"]);
+    assert_eq!(ancestor_keys(&map, None).count(), 0);
+}
+
+#[test]
+fn parent_chain_is_bounded_on_a_cycle() {
+    let parents: HashMap<&str, &str> = [("x", "y"), ("y", "x")].into();
+    let chain: Vec<_> = parent_chain(Some("x"), parents.len(), |k| parents.get(k).copied()).collect();
+    assert_eq!(chain, ["x", "y", "x"]);
+}
@@ -0,0 +1,124 @@
+mod common;
+
+use common::{Recorder, tree};
+use reconciler_core::{DiffOptions, LifecycleHook};
+
+const HOOKS: &[&str] = &["init_state", "did_change_dependencies", "deactivate", "activate", "did_update_widget", "dispose"];
+
+/// Lifecycle calls only, in order
+fn hooks(host: &Recorder) -> Vec<(&str, &str)> {
+    host.calls.iter()
+        .filter(|(m, _)| HOOKS.contains(m))
+        .map(|(m, k)| (*m, k.as_str()))
+        .collect()
+}
+
+#[test]
+fn mounts_run_parents_first_with_dependencies_after_init() {
+    let old = tree(&[("root", "Column", None)]);
+    let new = tree(&[
+        ("root", "Column", None),
+        ("a", "StatefulWidget", Some("root")),
+        ("a1", "Text", Some("a")),
+        ("b", "Text", Some("root")),
+    ]);
+
+    let mut host = Recorder::default();
+    let result = host.diff(&old, &new, DiffOptions::default());
+    assert!(result.lifecycle_errors.is_empty());
+    assert_eq!(hooks(&host), [
+        ("did_update_widget", "root"),
+        ("init_state", "a"),
+        ("did_change_dependencies", "a"),
+        ("init_state", "a1"),
+        ("did_change_dependencies", "a1"),
+        ("init_state", "b"),
+        ("did_change_dependencies", "b"),
+    ]);
+}
+
+#[test]
+fn removals_deactivate_then_dispose_children_first() {
+    let old = tree(&[
+        ("root", "Column", None),
+        ("a", "StatefulWidget", Some("root")),
+        ("a1", "Column", Some("a")),
+        ("a11", "Text", Some("a1")),
+        ("a2", "Text", Some("a")),
+    ]);
+    let new = tree(&[("root", "Column", None)]);
+
+    let mut host = Recorder::default();
+    host.diff(&old, &new, DiffOptions::default());
+    let calls = hooks(&host);
+    let pos = |hook: &str, key: &str| calls.iter().position(|c| *c == (hook, key)).unwrap();
+
+    let last_deactivate = calls.iter().rposition(|(m, _)| *m == "deactivate").unwrap();
+    let first_dispose = calls.iter().position(|(m, _)| *m == "dispose").unwrap();
+    assert!(last_deactivate < first_dispose, "{:?}", calls);
+
+    let mut disposed = host.keys("dispose");
+    assert_eq!(disposed.len(), 4);
+    for (child, parent) in [("a11", "a1"), ("a1", "a"), ("a2", "a")] {
+        assert!(pos("dispose", child) < pos("dispose", parent), "{:?}", calls);
+        assert!(pos("deactivate", child) < pos("deactivate", parent), "{:?}", calls);
+    }
+    disposed.sort_unstable();
+    assert_eq!(disposed, ["a", "a1", "a11", "a2"]);
+}
+
+#[test]
+fn reparented_nodes_are_activated_not_remounted() {
+    let old = tree(&[
+        ("root", "Column", None),
+        ("a", "Column", Some("root")),
+        ("x", "StatefulWidget", Some("a")),
+        ("b", "Column", Some("root")),
+    ]);
+    let new = tree(&[
+        ("root", "Column", None),
+        ("a", "Column", Some("root")),
+        ("b", "Column", Some("root")),
+        ("x", "StatefulWidget", Some("b")),
+    ]);
+
+    let mut host = Recorder::default();
+    host.diff(&old, &new, DiffOptions::default());
+    let calls = hooks(&host);
+    let x: Vec<&str> = calls.iter().filter(|(_, k)| *k == "x").map(|(m, _)| *m).collect();
+    assert_eq!(x, ["deactivate", "activate"]);
+}
+
+#[test]
+fn failing_hooks_are_collected_and_the_rest_still_run() {
+    let old = tree(&[
+        ("root", "Column", None),
+        ("gone", "Column", Some("root")),
+        ("gone1", "Text", Some("gone")),
+    ]);
+    let new = tree(&[
+        ("root", "Column", None),
+        ("a", "StatefulWidget", Some("root")),
+        ("a1", "Text", Some("a")),
+    ]);
+
+    let mut host = Recorder {
+        fail: vec![("init_state", "a".to_string()), ("dispose", "gone1".to_string())],
+        ..Recorder::default()
+    };
+    let result = host.diff(&old, &new, DiffOptions::default());
+
+    // No didChangeDependencies after a failed initState
+    assert_eq!(host.keys("did_change_dependencies"), ["a1"]);
+    assert_eq!(host.keys("init_state"), ["a", "a1"]);
+    assert_eq!(host.keys("dispose"), ["gone1", "gone"]);
+
+    let errors: Vec<(LifecycleHook, &str, &str)> = result.lifecycle_errors.iter()
+        .map(|e| (e.hook, e.key.as_str(), e.html_id.as_str()))
+        .collect();
+    assert_eq!(errors, [
+        (LifecycleHook::InitState, "a", "id_a"),
+        (LifecycleHook::Dispose, "gone1", "id_gone1"),
+    ]);
+    assert!(result.lifecycle_errors[0].message.contains("init_state failed"));
+}
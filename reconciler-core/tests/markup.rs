@@ -0,0 +1,72 @@
+use reconciler_core::html::decode_entities;
+use reconciler_core::markup::{
+    css_escape_value, html_escape, is_event_handler_attribute, js_string_escape, is_valid_attribute_name, is_valid_css_property,
+    is_valid_tag_name,
+};
+
+#[test]
+fn tag_names() {
+    for tag in ["div", "h1", "my-widget", "SVG"] {
+        assert!(is_valid_tag_name(tag), "{}", tag);
+    }
+    for tag in ["", "1h", "-x", "di v", "a>b", "x_y", "é"] {
+        assert!(!is_valid_tag_name(tag), "{}", tag);
+    }
+}
+
+#[test]
+fn attribute_names() {
+    for name in ["id", "data-fw-on-click", "aria-label", ":bind", "@click", "x.y", "é"] {
+        assert!(is_valid_attribute_name(name), "{}", name);
+    }
+    for name in ["", "a b", "a\"", "a'", "a=b", "<a", "a>", "a/b", "a\tb", "a\u{7}"] {
+        assert!(!is_valid_attribute_name(name), "{:?}", name);
+    }
+}
+
+#[test]
+fn event_handler_attributes_are_case_insensitive() {
+    for name in ["onclick", "ONCLICK", "onMouseOver", "on"] {
+        assert!(is_event_handler_attribute(name), "{}", name);
+    }
+    for name in ["", "o", "data-onclick", "é-on"] {
+        assert!(!is_event_handler_attribute(name), "{}", name);
+    }
+}
+
+#[test]
+fn css_property_names() {
+    for name in ["color", "background-color", "--my-var", "z-index"] {
+        assert!(is_valid_css_property(name), "{}", name);
+    }
+    for name in ["", "color:red", "a b", "a;b", "width!important", "x{"] {
+        assert!(!is_valid_css_property(name), "{}", name);
+    }
+}
+
+#[test]
+fn css_values_cannot_end_their_declaration() {
+    assert_eq!(css_escape_value("red"), "red");
+    assert_eq!(css_escape_value("red; background: url(x)"), r"red\; background: url(x)");
+    assert_eq!(css_escape_value("a}b{c"), r"a\}b\{c");
+    assert_eq!(css_escape_value(r"\;"), r"\\\;");
+    assert_eq!(css_escape_value("a\nb\u{0}c"), "abc");
+    assert_eq!(css_escape_value("'Noto Sans', é"), "'Noto Sans', é");
+}
+
+#[test]
+fn html_escape_round_trips_through_decode() {
+    let raw = r#"<a href="x">Tom & 'Jerry'</a>"#;
+    let escaped = html_escape(raw);
+    assert_eq!(escaped, "&lt;a href=&quot;x&quot;&gt;Tom &amp; &#x27;Jerry&#x27;&lt;/a&gt;");
+    assert_eq!(decode_entities(&escaped), raw);
+    assert_eq!(html_escape("&amp;"), "&amp;amp;");
+}
+
+#[test]
+fn js_strings_cannot_be_closed_or_broken() {
+    assert_eq!(js_string_escape("plain é"), "plain é");
+    assert_eq!(js_string_escape(r#"O'Brien "x" \"#), r#"O\'Brien \"x\" \\"#);
+    assert_eq!(js_string_escape("</script>"), r"\x3C/script>");
+    assert_eq!(js_string_escape("a\nb\r\u{2028}\u{2029}\u{1}"), r"a\nb\r\u2028\u2029\u0001");
+}
@@ -0,0 +1,61 @@
+mod common;
+
+use common::{Recorder, set_prop, tree};
+use reconciler_core::DiffOptions;
+use serde_json::json;
+
+/// root > m > [m1 > m2, m3]
+fn page() -> reconciler_core::NodeMap {
+    tree(&[
+        ("root", "Column", None),
+        ("m", "Column", Some("root")),
+        ("m1", "Column", Some("m")),
+        ("m2", "Text", Some("m1")),
+        ("m3", "Text", Some("m")),
+    ])
+}
+
+#[test]
+fn memoized_descendants_reuse_their_details() {
+    let old = page();
+    let mut new = page();
+    new.get_mut("m").unwrap().memoized = true;
+    set_prop(&mut new, "m2", "data", json!("not diffed"));
+
+    let mut host = Recorder::default();
+    let result = host.diff(&old, &new, DiffOptions { collect_stats: true, ..DiffOptions::default() });
+    assert!(result.patches.is_empty());
+    assert_eq!(host.keys("collect_details"), ["root", "m"]);
+    assert_eq!(host.keys("reuse_details"), ["m1", "m2", "m3"]);
+    // Nothing below root was rebuilt
+    assert_eq!(host.keys("did_update_widget"), ["root"]);
+    assert!(result.new_rendered_map.values().all(|n| !n.memoized));
+    assert_eq!(result.stats.unwrap().kept_subtrees, 1);
+}
+
+#[test]
+fn hash_kept_subtrees_collect_details_afresh() {
+    let mut old = page();
+    old.get_mut("m").unwrap().subtree_hash = Some(7);
+    let new = old.clone();
+
+    let mut host = Recorder::default();
+    host.diff(&old, &new, DiffOptions::default());
+    assert_eq!(host.keys("collect_details"), ["root", "m", "m1", "m2", "m3"]);
+    assert!(host.keys("reuse_details").is_empty());
+    assert_eq!(host.keys("did_update_widget"), ["root", "m", "m1", "m2", "m3"]);
+}
+
+#[test]
+fn memoized_nodes_inside_a_hash_kept_subtree_still_reuse() {
+    let mut old = page();
+    old.get_mut("root").unwrap().subtree_hash = Some(7);
+    let mut new = old.clone();
+    new.get_mut("m1").unwrap().memoized = true;
+
+    let mut host = Recorder::default();
+    host.diff(&old, &new, DiffOptions::default());
+    assert_eq!(host.keys("collect_details"), ["root", "m", "m1", "m3"]);
+    assert_eq!(host.keys("reuse_details"), ["m2"]);
+    assert_eq!(host.keys("did_update_widget"), ["root", "m", "m3"]);
+}
@@ -0,0 +1,71 @@
+#![cfg(feature = "msgpack")]
+use reconciler_core::msgpack::{decode_payload, encode_payload};
+use reconciler_core::{JsInitializer, PatchAction, PatchPayload, ReconcilerError, RustPatch};
+use serde_json::json;
+
+fn sample_payload() -> PatchPayload {
+    let long_text = "x".repeat(300);
+    PatchPayload {
+        patches: vec![
+            RustPatch {
+                action: PatchAction::Insert,
+                html_id: "fw_id_1".into(),
+                data: json!({
+                    "html": "<p id=\"fw_id_1\">héllo</p>",
+                    "parent_html_id": "root-container",
+                    "before_id": null,
+                    "props": {"width": 12.5, "count": 300, "offset": -70000, "flags": [true, false], "text": long_text},
+                }),
+            },
+            RustPatch { action: PatchAction::Remove, html_id: "fw_id_2".into(), data: json!(null) },
+            RustPatch { action: PatchAction::Update, html_id: "fw_id_3".into(), data: json!({"props": {"n": u64::MAX, "m": i64::MIN}}) },
+            RustPatch { action: PatchAction::Move, html_id: "fw_id_4".into(), data: json!({"before_id": "fw_id_5"}) },
+            RustPatch { action: PatchAction::Replace, html_id: "fw_id_6".into(), data: json!({"new_html": ""}) },
+        ],
+        js_initializers: vec![
+            JsInitializer { init_type: "SimpleBar".into(), target_id: "fw_id_1".into(), data: json!({}), before_id: None },
+            JsInitializer { init_type: "generic".into(), target_id: "fw_id_4".into(), data: json!([1, 2, 3]), before_id: Some("fw_id_5".into()) },
+        ],
+    }
+}
+
+#[test]
+fn payload_round_trips() {
+    let payload = sample_payload();
+    let bytes = encode_payload(&payload.patches, &payload.js_initializers);
+    assert_eq!(decode_payload(&bytes).unwrap(), payload);
+}
+
+#[test]
+fn msgpack_is_smaller_than_json() {
+    let payload = sample_payload();
+    let bytes = encode_payload(&payload.patches, &payload.js_initializers);
+    let json = reconciler_core::patches_to_json_bytes(&payload.patches, &payload.js_initializers).unwrap();
+    assert!(bytes.len() < json.len());
+}
+
+#[test]
+fn truncated_input_is_rejected() {
+    let payload = sample_payload();
+    let bytes = encode_payload(&payload.patches, &payload.js_initializers);
+    assert!(decode_payload(&bytes[..bytes.len() - 1]).is_err());
+    assert!(decode_payload(&[0x93, 0x02, 0x90, 0x90]).is_err());
+}
+
+#[test]
+fn deep_nesting_is_rejected_without_overflowing() {
+    let bytes = vec![0x91; 1_000_000];
+    let err = decode_payload(&bytes).unwrap_err();
+    assert!(matches!(err, ReconcilerError::DecodeError { .. }), "{:?}", err);
+}
+
+#[test]
+fn nesting_below_the_limit_round_trips() {
+    let mut data = json!("leaf");
+    for _ in 0..100 {
+        data = json!([data]);
+    }
+    let patches = vec![RustPatch { action: PatchAction::Update, html_id: "fw_id_1".into(), data }];
+    let bytes = encode_payload(&patches, &[]);
+    assert_eq!(decode_payload(&bytes).unwrap().patches, patches);
+}
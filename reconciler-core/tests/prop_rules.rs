@@ -0,0 +1,106 @@
+use reconciler_core::{PropComparator, PropDiffRules};
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+fn props(pairs: &[(&str, Value)]) -> HashMap<String, Value> {
+    pairs.iter().map(|(k, v)| (k.to_string(), v.clone())).collect()
+}
+
+/// Sorted changed keys and the removed keys
+fn changes(rules: &PropDiffRules, widget_type: &str, old: &[(&str, Value)], new: &[(&str, Value)]) -> (Vec<String>, Vec<String>) {
+    let diff = rules.diff(widget_type, &props(old), &props(new));
+    let mut changed: Vec<String> = diff.changed.into_keys().collect();
+    changed.sort();
+    (changed, diff.removed)
+}
+
+fn with_comparator(prop: &str, comparator: PropComparator) -> PropDiffRules {
+    let mut rules = PropDiffRules::default();
+    rules.comparators.insert(prop.to_string(), comparator);
+    rules
+}
+
+#[test]
+fn added_changed_and_removed_keys() {
+    let rules = PropDiffRules::default();
+    let (changed, removed) = changes(
+        &rules, "Text",
+        &[("same", json!(1)), ("changed", json!("a")), ("z_gone", json!(1)), ("a_gone", json!(null))],
+        &[("same", json!(1)), ("changed", json!("b")), ("added", json!(null))],
+    );
+    assert_eq!(changed, ["added", "changed"]);
+    assert_eq!(removed, ["a_gone", "z_gone"]);
+}
+
+#[test]
+fn ignored_props_are_never_reported() {
+    let mut rules = PropDiffRules::default();
+    rules.ignored_by_type.entry("Text".to_string()).or_default().insert("data".to_string());
+    let old = [("onPressed", json!("f")), ("data", json!("a")), ("widget_instance", json!(1))];
+    let new = [("onPressed", json!("g")), ("data", json!("b"))];
+
+    assert_eq!(changes(&rules, "Text", &old, &new), (vec![], vec![]));
+    assert_eq!(changes(&rules, "Column", &old, &new), (vec!["data".to_string()], vec![]));
+}
+
+#[test]
+fn exact_is_plain_equality() {
+    let rules = with_comparator("n", PropComparator::Exact);
+    assert_eq!(changes(&rules, "X", &[("n", json!(1))], &[("n", json!(1.0))]).0, ["n"]);
+    assert!(changes(&rules, "X", &[("n", json!([1, 2]))], &[("n", json!([1, 2]))]).0.is_empty());
+}
+
+#[test]
+fn epsilon_tolerates_small_number_changes_at_any_depth() {
+    let eps = PropComparator::Epsilon(0.01);
+    assert!(eps.equal(&json!(1.0), &json!(1.005)));
+    assert!(eps.equal(&json!(1), &json!(1.0)));
+    assert!(eps.equal(&json!({ "x": [0.5, 2] }), &json!({ "x": [0.509, 2.0] })));
+    assert!(!eps.equal(&json!(1.0), &json!(1.02)));
+    assert!(!eps.equal(&json!({ "x": 1 }), &json!({ "x": 1, "y": 1 })));
+    assert!(!eps.equal(&json!([1]), &json!([1, 1])));
+    assert!(!eps.equal(&json!("1"), &json!(1)));
+}
+
+#[test]
+fn unordered_compares_class_lists_as_sets() {
+    let unordered = PropComparator::Unordered;
+    assert!(unordered.equal(&json!("a b  c"), &json!("c a b")));
+    assert!(unordered.equal(&json!(["a", "b"]), &json!(["b", "a", "a"])));
+    assert!(unordered.equal(&json!("a b"), &json!(["b", "a"])));
+    assert!(!unordered.equal(&json!("a b"), &json!("a")));
+    assert!(!unordered.equal(&json!(1), &json!(2)));
+    assert!(unordered.equal(&json!(null), &json!(null)));
+}
+
+#[test]
+fn ignore_nulls_treats_null_fields_as_absent() {
+    let ignore_nulls = PropComparator::IgnoreNulls;
+    assert!(ignore_nulls.equal(&json!({ "a": 1, "b": null }), &json!({ "a": 1 })));
+    assert!(ignore_nulls.equal(&json!([{ "a": null }]), &json!([{}])));
+    assert!(!ignore_nulls.equal(&json!({ "a": 1 }), &json!({ "a": 2, "b": null })));
+    assert!(!ignore_nulls.equal(&json!({ "a": 1 }), &json!({ "b": 1 })));
+    assert!(!ignore_nulls.equal(&json!([null]), &json!([])));
+}
+
+#[test]
+fn custom_comparators_decide_alone() {
+    let rules = with_comparator("v", PropComparator::Custom(Arc::new(|old, new| old["id"] == new["id"])));
+    assert!(changes(&rules, "X", &[("v", json!({ "id": 1, "x": 1 }))], &[("v", json!({ "id": 1, "x": 2 }))]).0.is_empty());
+    assert_eq!(changes(&rules, "X", &[("v", json!({ "id": 1 }))], &[("v", json!({ "id": 2 }))]).0, ["v"]);
+}
+
+#[test]
+fn per_type_comparators_take_precedence() {
+    let mut rules = with_comparator("size", PropComparator::Epsilon(1.0));
+    rules.comparators_by_type.entry("Text".to_string()).or_default()
+        .insert("size".to_string(), PropComparator::Exact);
+    let (old, new) = ([("size", json!(10.0))], [("size", json!(10.5))]);
+
+    assert_eq!(changes(&rules, "Text", &old, &new).0, ["size"]);
+    assert!(changes(&rules, "Column", &old, &new).0.is_empty());
+    assert!(matches!(rules.comparator("Text", "size"), Some(PropComparator::Exact)));
+    assert!(matches!(rules.comparator("Column", "size"), Some(PropComparator::Epsilon(_))));
+    assert!(rules.comparator("Text", "other").is_none());
+}
@@ -0,0 +1,186 @@
+mod common;
+
+use common::tree;
+use reconciler_core::{
+    DiffHost, DiffOptions, NodeMap, NullHost, PatchAction, ReconcilerError, RustNodeData,
+    RustReconciliationResult, reconcile,
+};
+use std::collections::HashSet;
+
+#[derive(Default)]
+struct DisposeCounter {
+    disposed: Vec<String>,
+}
+
+impl DiffHost for DisposeCounter {
+    type Widget = ();
+    type Error = ReconcilerError;
+
+    fn dispose(&mut self, node: &RustNodeData) -> Result<(), ReconcilerError> {
+        self.disposed.push(node.key.clone());
+        Ok(())
+    }
+}
+
+fn run<H: DiffHost<Widget = ()>>(host: &mut H, old: &NodeMap, new: &NodeMap, partial: bool) -> RustReconciliationResult {
+    reconcile(host, old, new, "body", partial, Some("root".to_string()), DiffOptions::default())
+        .unwrap_or_else(|_| panic!("reconcile failed"))
+}
+
+fn removed_ids(result: &RustReconciliationResult) -> Vec<&str> {
+    let ids: Vec<&str> = result.patches.iter()
+        .filter(|p| p.action == PatchAction::Remove)
+        .map(|p| p.html_id.as_str())
+        .collect();
+    let unique: HashSet<_> = ids.iter().collect();
+    assert_eq!(unique.len(), ids.len(), "html_id removed twice: {:?}", ids);
+    ids
+}
+
+fn sorted(mut keys: Vec<String>) -> Vec<String> {
+    keys.sort();
+    keys
+}
+
+#[test]
+fn removed_subtree_gets_one_remove_at_its_root() {
+    let old = tree(&[
+        ("root", "Column", None),
+        ("a", "Column", Some("root")),
+        ("a1", "Text", Some("a")),
+        ("a2", "Column", Some("a")),
+        ("a21", "Text", Some("a2")),
+        ("b", "Text", Some("root")),
+    ]);
+    let new = tree(&[("root", "Column", None), ("b", "Text", Some("root"))]);
+
+    let mut host = DisposeCounter::default();
+    let result = run(&mut host, &old, &new, false);
+    assert_eq!(removed_ids(&result), ["id_a"]);
+    assert_eq!(sorted(host.disposed), ["a", "a1", "a2", "a21"]);
+}
+
+#[test]
+fn partial_reconciliation_removes_once_too() {
+    let old = tree(&[
+        ("root", "Column", None),
+        ("a", "Column", Some("root")),
+        ("a1", "Text", Some("a")),
+    ]);
+    let new = tree(&[("root", "Column", None)]);
+
+    let result = run(&mut NullHost, &old, &new, true);
+    assert_eq!(removed_ids(&result), ["id_a"]);
+}
+
+#[test]
+fn transparent_widgets_pass_the_remove_to_their_children() {
+    let old = tree(&[
+        ("root", "Column", None),
+        ("s", "StatelessWidget", Some("root")),
+        ("s1", "Text", Some("s")),
+        ("s2", "Column", Some("s")),
+        ("s21", "Text", Some("s2")),
+    ]);
+    let new = tree(&[("root", "Column", None)]);
+
+    let mut host = DisposeCounter::default();
+    let result = run(&mut host, &old, &new, false);
+    let mut ids = removed_ids(&result);
+    ids.sort();
+    assert_eq!(ids, ["id_s1", "id_s2"]);
+    assert_eq!(sorted(host.disposed), ["s", "s1", "s2", "s21"]);
+}
+
+#[test]
+fn replaced_node_takes_its_old_children_along() {
+    let old = tree(&[
+        ("root", "Column", None),
+        ("a", "Column", Some("root")),
+        ("a1", "Text", Some("a")),
+        ("a2", "Text", Some("a")),
+    ]);
+    let new = tree(&[("root", "Column", None), ("a", "Row", Some("root"))]);
+
+    let mut host = DisposeCounter::default();
+    let result = run(&mut host, &old, &new, false);
+    assert!(removed_ids(&result).is_empty());
+    assert!(result.patches.iter().any(|p| p.action == PatchAction::Replace && p.html_id == "id_a"));
+    assert_eq!(sorted(host.disposed), ["a", "a1", "a2"]);
+}
+
+#[test]
+fn reparented_node_is_removed_and_inserted_but_not_disposed() {
+    let under_a = tree(&[
+        ("root", "Column", None),
+        ("a", "Column", Some("root")),
+        ("x", "Text", Some("a")),
+        ("b", "Column", Some("root")),
+    ]);
+    let under_b = tree(&[
+        ("root", "Column", None),
+        ("a", "Column", Some("root")),
+        ("b", "Column", Some("root")),
+        ("x", "Text", Some("b")),
+    ]);
+
+    // Both directions: the old element must be gone before the one with the
+    // same id is inserted, whichever parent the diff visits first
+    for (old, new, parent) in [(&under_a, &under_b, "id_b"), (&under_b, &under_a, "id_a")] {
+        let mut host = DisposeCounter::default();
+        let result = run(&mut host, old, new, false);
+        assert_eq!(removed_ids(&result), ["id_x"]);
+        let position = |action| result.patches.iter()
+            .position(|p| p.action == action && p.html_id == "id_x")
+            .unwrap_or_else(|| panic!("no {} for id_x in {:?}", action, result.patches));
+        assert!(position(PatchAction::Remove) < position(PatchAction::Insert), "{:?}", result.patches);
+        let insert = &result.patches[position(PatchAction::Insert)];
+        assert_eq!(insert.data["parent_html_id"], parent);
+        assert!(host.disposed.is_empty());
+    }
+}
+
+#[test]
+fn unchanged_tree_removes_nothing() {
+    let rows = [
+        ("root", "Column", None),
+        ("a", "Column", Some("root")),
+        ("a1", "Text", Some("a")),
+    ];
+    let result = run(&mut NullHost, &tree(&rows), &tree(&rows), false);
+    assert!(removed_ids(&result).is_empty());
+}
+
+#[test]
+fn reparented_subtree_keeps_its_children_under_it() {
+    let under_a = tree(&[
+        ("root", "Column", None),
+        ("a", "Column", Some("root")),
+        ("x", "Column", Some("a")),
+        ("x1", "Text", Some("x")),
+        ("x2", "Column", Some("x")),
+        ("x21", "Text", Some("x2")),
+        ("c", "Column", Some("root")),
+    ]);
+    let under_c = tree(&[
+        ("root", "Column", None),
+        ("a", "Column", Some("root")),
+        ("c", "Column", Some("root")),
+        ("x", "Column", Some("c")),
+        ("x1", "Text", Some("x")),
+        ("x2", "Column", Some("x")),
+        ("x21", "Text", Some("x2")),
+    ]);
+
+    for (old, new, parent) in [(&under_a, &under_c, "id_c"), (&under_c, &under_a, "id_a")] {
+        let mut host = DisposeCounter::default();
+        let result = run(&mut host, old, new, false);
+        assert_eq!(removed_ids(&result), ["id_x"]);
+        let inserted: Vec<(&str, &str)> = result.patches.iter()
+            .filter(|p| p.action == PatchAction::Insert)
+            .map(|p| (p.html_id.as_str(), p.data["parent_html_id"].as_str().unwrap_or("")))
+            .collect();
+        assert_eq!(inserted, [("id_x", parent), ("id_x1", "id_x"), ("id_x2", "id_x"), ("id_x21", "id_x2")]);
+        assert!(host.disposed.is_empty());
+    }
+}
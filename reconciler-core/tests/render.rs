@@ -0,0 +1,98 @@
+mod common;
+
+use common::tree;
+use reconciler_core::{NodeMap, RenderChunks, render_to_string, split_stub};
+
+/// root > [a (Text) > a1, s (StatefulWidget) > [s1, img (Image, void)], b]
+fn page() -> NodeMap {
+    let mut map = tree(&[
+        ("root", "Column", None),
+        ("a", "Text", Some("root")),
+        ("a1", "Text", Some("a")),
+        ("s", "StatefulWidget", Some("root")),
+        ("s1", "Text", Some("s")),
+        ("img", "Image", Some("s")),
+        ("b", "Text", Some("root")),
+    ]);
+    map.get_mut("a").unwrap().html = r#"<p id="id_a">hi</p>"#.to_string();
+    map.get_mut("img").unwrap().html = r#"<img id="id_img" alt="">"#.to_string();
+    map.get_mut("s").unwrap().html = String::new();
+    map
+}
+
+#[test]
+fn children_nest_inside_their_parent_after_its_content() {
+    assert_eq!(
+        render_to_string(&page(), "root"),
+        concat!(
+            r#"<div id="id_root">"#,
+            r#"<p id="id_a">hi<div id="id_a1"></div></p>"#,
+            r#"<div id="id_s1"></div><img id="id_img" alt="">"#,
+            r#"<div id="id_b"></div>"#,
+            "</div>",
+        ),
+    );
+}
+
+#[test]
+fn transparent_root_renders_only_its_children() {
+    let map = page();
+    assert_eq!(render_to_string(&map, "s"), r#"<div id="id_s1"></div><img id="id_img" alt="">"#);
+}
+
+#[test]
+fn void_elements_drop_their_children() {
+    let mut map = page();
+    map.get_mut("img").unwrap().children_keys.push("b".to_string());
+    assert_eq!(render_to_string(&map, "img"), r#"<img id="id_img" alt="">"#);
+}
+
+#[test]
+fn missing_keys_are_skipped() {
+    let mut map = page();
+    map.get_mut("root").unwrap().children_keys.insert(0, "ghost".to_string());
+    assert!(render_to_string(&map, "root").starts_with(r#"<div id="id_root"><p id="id_a">"#));
+    assert_eq!(render_to_string(&map, "ghost"), "");
+}
+
+#[test]
+fn split_stub_separates_the_closing_tag() {
+    assert_eq!(split_stub(r#"<p id="x">hi</p>"#), (r#"<p id="x">hi"#, "</p>"));
+    assert_eq!(split_stub("<DIV>a</div>  "), ("<DIV>a", "</div>"));
+    assert_eq!(split_stub(r#"<img id="x">"#), (r#"<img id="x">"#, ""));
+    assert_eq!(split_stub(""), ("", ""));
+}
+
+#[test]
+fn non_ascii_stubs_split_on_char_boundaries() {
+    assert_eq!(split_stub(r#"<img id="fw_id_2" alt="日本">"#), (r#"<img id="fw_id_2" alt="日本">"#, ""));
+    assert_eq!(split_stub("<p>日本語</p>"), ("<p>日本語", "</p>"));
+    assert_eq!(split_stub("<p>é"), ("<p>é", ""));
+
+    let mut map = page();
+    map.get_mut("img").unwrap().html = r#"<img id="id_img" alt="日本">"#.to_string();
+    map.get_mut("a").unwrap().html = r#"<p id="id_a">héllo</p>"#.to_string();
+    let html = render_to_string(&map, "root");
+    assert!(html.contains(r#"<img id="id_img" alt="日本">"#), "{}", html);
+    assert!(html.contains(r#"<p id="id_a">héllo<div id="id_a1"></div></p>"#), "{}", html);
+    let chunks: Vec<String> = RenderChunks::new(&map, "root").collect();
+    assert_eq!(chunks.concat(), html);
+}
+
+#[test]
+fn chunks_concatenate_to_the_rendered_string() {
+    let map = page();
+    for root in ["root", "a", "s", "img", "ghost"] {
+        let chunks: Vec<String> = RenderChunks::new(&map, root).collect();
+        assert_eq!(chunks.concat(), render_to_string(&map, root), "root {}", root);
+    }
+}
+
+#[test]
+fn chunks_stream_open_and_close_tags_separately() {
+    let map = page();
+    let chunks: Vec<String> = RenderChunks::new(&map, "root").collect();
+    assert_eq!(chunks.first().map(String::as_str), Some(r#"<div id="id_root">"#));
+    assert_eq!(chunks.last().map(String::as_str), Some("</div>"));
+    assert!(chunks.iter().all(|c| !c.is_empty()), "{:?}", chunks);
+}
@@ -0,0 +1,125 @@
+mod common;
+
+use common::{Recorder, diff, set_prop, tree};
+use reconciler_core::{DiffOptions, NodeMap, compute_subtree_hashes};
+use serde_json::json;
+
+/// root > [a > a1, b]
+fn page() -> NodeMap {
+    tree(&[
+        ("root", "Column", None),
+        ("a", "Column", Some("root")),
+        ("a1", "Text", Some("a")),
+        ("b", "Text", Some("root")),
+    ])
+}
+
+fn hashed(mut map: NodeMap) -> NodeMap {
+    compute_subtree_hashes(&mut map);
+    map
+}
+
+fn hash(map: &NodeMap, key: &str) -> u64 {
+    map[key].subtree_hash.expect("hashed node")
+}
+
+#[test]
+fn every_node_gets_a_hash_independent_of_html_ids() {
+    let map = hashed(page());
+    assert!(map.values().all(|n| n.subtree_hash.is_some()));
+
+    let mut moved = page();
+    for node in moved.values_mut() {
+        node.html_id = format!("other_{}", node.key);
+    }
+    let moved = hashed(moved);
+    assert!(map.keys().all(|k| hash(&map, k) == hash(&moved, k)));
+}
+
+#[test]
+fn prop_and_object_key_order_do_not_matter() {
+    let mut one = page();
+    set_prop(&mut one, "a1", "x", json!(1));
+    set_prop(&mut one, "a1", "style", json!({ "color": "red", "margin": 0 }));
+    let mut other = page();
+    set_prop(&mut other, "a1", "style", json!({ "margin": 0, "color": "red" }));
+    set_prop(&mut other, "a1", "x", json!(1));
+
+    let (one, other) = (hashed(one), hashed(other));
+    assert_eq!(hash(&one, "root"), hash(&other, "root"));
+}
+
+#[test]
+fn a_descendant_change_reaches_every_ancestor_only() {
+    let before = hashed(page());
+    let mut changed = page();
+    set_prop(&mut changed, "a1", "data", json!("new"));
+    let after = hashed(changed);
+
+    for key in ["root", "a", "a1"] {
+        assert_ne!(hash(&before, key), hash(&after, key), "{}", key);
+    }
+    assert_eq!(hash(&before, "b"), hash(&after, "b"));
+}
+
+#[test]
+fn child_order_and_types_change_the_hash() {
+    let before = hashed(page());
+
+    let mut reordered = page();
+    reordered.get_mut("root").unwrap().children_keys.reverse();
+    assert_ne!(hash(&before, "root"), hash(&hashed(reordered), "root"));
+
+    let mut retyped = page();
+    retyped.get_mut("b").unwrap().widget_type = "Icon".to_string();
+    assert_ne!(hash(&before, "root"), hash(&hashed(retyped), "root"));
+}
+
+#[test]
+fn memoized_nodes_keep_their_hash() {
+    let mut map = page();
+    map.get_mut("a").unwrap().memoized = true;
+    map.get_mut("a").unwrap().subtree_hash = Some(42);
+    let map = hashed(map);
+    assert_eq!(hash(&map, "a"), 42);
+    assert_eq!(map["a1"].subtree_hash, None);
+}
+
+#[test]
+fn equal_hashes_skip_prop_diffs_but_not_hooks() {
+    let old = hashed(page());
+    let mut new = hashed(page());
+    // Props that would produce an UPDATE if the subtree were diffed
+    set_prop(&mut new, "a1", "data", json!("not diffed"));
+
+    let mut host = Recorder::default();
+    let result = host.diff(&old, &new, DiffOptions { collect_stats: true, ..DiffOptions::default() });
+    assert!(result.patches.is_empty());
+    assert_eq!(result.stats.unwrap().kept_subtrees, 1);
+    assert_eq!(result.new_rendered_map["a1"].props["data"], "not diffed");
+
+    // The widgets were rebuilt, so every node still gets its details and update
+    assert_eq!(host.keys("collect_details"), ["root", "a", "a1", "b"]);
+    assert_eq!(host.keys("did_update_widget"), ["root", "a", "a1", "b"]);
+}
+
+#[test]
+fn hash_changes_or_new_ids_are_diffed() {
+    let old = hashed(page());
+    let mut changed = page();
+    set_prop(&mut changed, "a1", "data", json!("new"));
+    let changed = hashed(changed);
+
+    let mut host = Recorder::default();
+    let result = host.diff(&old, &changed, DiffOptions { collect_stats: true, ..DiffOptions::default() });
+    assert_eq!(result.patches.len(), 1);
+    // b is still identical and kept whole, but updated like its siblings
+    assert_eq!(result.stats.unwrap().kept_subtrees, 1);
+    assert_eq!(host.keys("did_update_widget"), ["root", "a", "a1", "b"]);
+
+    // Same content under another element is diffed; its children are kept
+    let mut renamed = old.clone();
+    renamed.get_mut("root").unwrap().html_id = "id_root2".to_string();
+    let result = diff(&old, &renamed, DiffOptions { collect_stats: true, ..DiffOptions::default() });
+    assert_eq!(result.stats.unwrap().kept_subtrees, 2);
+}
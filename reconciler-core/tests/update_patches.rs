@@ -0,0 +1,63 @@
+mod common;
+
+use common::{diff, set_prop, summary, tree};
+use reconciler_core::{DiffOptions, NodeMap};
+use serde_json::json;
+
+/// root > a (Text) with `old_props` before and `new_props` after
+fn text_change(old_props: &[(&str, serde_json::Value)], new_props: &[(&str, serde_json::Value)]) -> (NodeMap, NodeMap) {
+    let rows = [("root", "Column", None), ("a", "Text", Some("root"))];
+    let (mut old, mut new) = (tree(&rows), tree(&rows));
+    for (prop, value) in old_props {
+        set_prop(&mut old, "a", prop, value.clone());
+    }
+    for (prop, value) in new_props {
+        set_prop(&mut new, "a", prop, value.clone());
+    }
+    (old, new)
+}
+
+#[test]
+fn update_carries_only_changed_and_removed_props() {
+    let (old, new) = text_change(
+        &[("data", json!("hi")), ("color", json!("red")), ("size", json!(12)), ("gone", json!(1))],
+        &[("data", json!("hi")), ("color", json!("blue")), ("size", json!(12)), ("added", json!(true))],
+    );
+
+    let result = diff(&old, &new, DiffOptions::default());
+    assert_eq!(summary(&result.patches), [("UPDATE".to_string(), "id_a")]);
+    assert_eq!(
+        result.patches[0].data,
+        json!({ "props": { "color": "blue", "added": true }, "removed_props": ["gone"] }),
+    );
+}
+
+#[test]
+fn full_props_sends_both_complete_prop_sets() {
+    let (old, new) = text_change(&[("data", json!("a")), ("gone", json!(1))], &[("data", json!("b"))]);
+
+    let options = DiffOptions { full_props: true, ..DiffOptions::default() };
+    let result = diff(&old, &new, options);
+    assert_eq!(
+        result.patches[0].data,
+        json!({ "props": { "data": "b" }, "old_props": { "data": "a", "gone": 1 } }),
+    );
+}
+
+#[test]
+fn unchanged_or_ignored_props_send_no_update() {
+    let (old, new) = text_change(
+        &[("data", json!("a")), ("onPressed", json!("f"))],
+        &[("data", json!("a")), ("onPressed", json!("g"))],
+    );
+    assert!(diff(&old, &new, DiffOptions::default()).patches.is_empty());
+}
+
+#[test]
+fn transparent_widgets_get_no_update() {
+    let rows = [("root", "Column", None), ("s", "StatefulWidget", Some("root"))];
+    let (mut old, mut new) = (tree(&rows), tree(&rows));
+    set_prop(&mut old, "s", "count", json!(1));
+    set_prop(&mut new, "s", "count", json!(2));
+    assert!(diff(&old, &new, DiffOptions::default()).patches.is_empty());
+}
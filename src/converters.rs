@@ -26,13 +26,13 @@ pub fn py_dict_to_rust_map<'py>(
 
 /// Convert Python object to JSON with full type support
 pub fn python_to_json<'py>(
-    py: Python<'py>, 
+    _py: Python<'py>, 
     obj: &Bound<'py, PyAny>
 ) -> Result<serde_json::Value, ReconcilerError> {
     use serde_json::Value;
 
     // Recursive conversion from Python object to serde_json::Value
-    fn convert<'py>(py: Python<'py>, obj: &Bound<'py, PyAny>) -> Result<Value, ReconcilerError> {
+    fn convert(obj: &Bound<'_, PyAny>) -> Result<Value, ReconcilerError> {
         // None
         if obj.is_none() {
             return Ok(Value::Null);
@@ -63,7 +63,7 @@ pub fn python_to_json<'py>(
         if let Ok(list) = obj.cast::<PyList>() {
             let mut vec = Vec::with_capacity(list.len());
             for item in list.iter() {
-                vec.push(convert(py, &item)?);
+                vec.push(convert(&item)?);
             }
             return Ok(Value::Array(vec));
         }
@@ -74,10 +74,10 @@ pub fn python_to_json<'py>(
             for (k, v) in dict {
                 // stringify key
                 let key = match k.str() {
-                    Ok(pystr) => pystr.to_str().map(|s| s.to_string()).unwrap_or_else(|_| format!("{}", k.repr().map(|r| r.to_string()).unwrap_or_default())),
-                    Err(_) => format!("{}", k.repr().map(|r| r.to_string()).unwrap_or_default()),
+                    Ok(pystr) => pystr.to_str().map(|s| s.to_string()).unwrap_or_else(|_| k.repr().map(|r| r.to_string()).unwrap_or_default()),
+                    Err(_) => k.repr().map(|r| r.to_string()).unwrap_or_default(),
                 };
-                let val = convert(py, &v)?;
+                let val = convert(&v)?;
                 map.insert(key, val);
             }
             return Ok(Value::Object(map));
@@ -99,7 +99,7 @@ pub fn python_to_json<'py>(
         }
     }
 
-    convert(py, obj)
+    convert(obj)
 }
 
 /// Convert JSON back to Python with proper type mapping
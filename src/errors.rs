@@ -3,44 +3,58 @@
 use pyo3::{exceptions::PyValueError, PyErr};
 use thiserror::Error;
 
+#[allow(clippy::enum_variant_names)]
 #[derive(Error, Debug)]
 pub enum ReconcilerError {
     #[error("Key extraction failed: {details}")]
     KeyError { details: String },
-    
-    #[error("Property error for '{property}': {details}")]
-    PropError { property: String, details: String },
-    
+
     #[error("Type conversion error: expected {expected}, got {actual}")]
     TypeConversionError { expected: String, actual: String },
-    
+
     #[error("HTML generation failed for widget '{widget_type}': {details}")]
     HtmlGenerationError { widget_type: String, details: String },
-    
+
+    #[error("Invalid widget template: {details}")]
+    TemplateError { details: String },
+
     #[error("Serialization error: {0}")]
     SerdeError(#[from] serde_json::Error),
-    
+
+    /// Two widgets in one tree resolved to the same key; paths are the
+    /// key chains from the root, e.g. `root > column > item`
+    #[error("Duplicate key '{key}': {second_type} at {second_path} collides with {first_type} at {first_path}")]
+    DuplicateKey {
+        key: String,
+        first_type: String,
+        first_path: String,
+        second_type: String,
+        second_path: String,
+    },
+
     #[error("Python call failed: {0}")]
     PythonError(String),
+
+    #[error(transparent)]
+    CoreError(#[from] reconciler_core::ReconcilerError),
 }
 
 // Helper macro for safe key extraction
-// near the top of src/errors.rs, after the error enum
 #[macro_export]
 macro_rules! safe_get {
     ($dict:expr, $key:expr, $ty:ty) => {{
         // get_item returns Result<Option<...>, PyErr>, so unwrap the Result first
-        let item = ($dict.get_item($key)?) .ok_or_else(|| crate::errors::ReconcilerError::KeyError {
+        let item = ($dict.get_item($key)?) .ok_or_else(|| $crate::errors::ReconcilerError::KeyError {
             details: format!("Missing key '{}'", $key),
         })?;
-        item.extract::<$ty>().map_err(|e| crate::errors::ReconcilerError::TypeConversionError {
+        item.extract::<$ty>().map_err(|e| $crate::errors::ReconcilerError::TypeConversionError {
             expected: stringify!($ty).to_string(),
             actual: e.to_string(),
         })?
     }};
     ($dict:expr, $key:expr, $ty:ty, $default:expr) => {{
         match $dict.get_item($key)? {
-            Some(val) => val.extract::<$ty>().map_err(|e| crate::errors::ReconcilerError::TypeConversionError {
+            Some(val) => val.extract::<$ty>().map_err(|e| $crate::errors::ReconcilerError::TypeConversionError {
                 expected: stringify!($ty).to_string(),
                 actual: e.to_string(),
             }),
@@ -61,7 +75,3 @@ impl From<PyErr> for ReconcilerError {
         ReconcilerError::PythonError(err.to_string())
     }
 }
-
-
-
-
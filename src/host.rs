@@ -0,0 +1,207 @@
+//! PyO3 implementation of the core `DiffHost`: HTML stubs, CSS/callback
+//! collection and StatefulWidget lifecycle calls go back into Python. Lifecycle
+//! hooks are looked up on `widget.get_state()`; missing ones are skipped
+use crate::converters::json_to_pyobject;
+use crate::errors::ReconcilerError;
+use crate::html_generator::{StubConfig, generate_html_stub, map_to_json_value};
+use crate::types::{DetailsCache, NodeDetails, PyDetails, PyObjectWrapper, PyStats, RustNodeData};
+use pyo3::prelude::*;
+use pyo3::call::PyCallArgs;
+use reconciler_core::DiffHost;
+use reconciler_core::events::{EventMode, event_bindings};
+
+pub struct PyHost<'a, 'py> {
+    py: Python<'py>,
+    config: &'a StubConfig,
+    pub details: PyDetails,
+    pub stats: PyStats,
+    /// Details of earlier calls, updated with every node collected or disposed
+    pub cache: DetailsCache,
+}
+
+impl<'a, 'py> PyHost<'a, 'py> {
+    /// Host that keeps accumulating into `stats` (started by the tree builder)
+    pub fn new(py: Python<'py>, config: &'a StubConfig, stats: PyStats) -> Self {
+        PyHost { py, config, details: PyDetails::default(), stats, cache: DetailsCache::new() }
+    }
+
+    /// Start from the details cache of earlier calls
+    pub fn with_cache(mut self, cache: DetailsCache) -> Self {
+        self.cache = cache;
+        self
+    }
+
+    fn clone_ref(&self, object: &PyObjectWrapper) -> PyObjectWrapper {
+        PyObjectWrapper(object.0.clone_ref(self.py))
+    }
+
+    /// Add one node's CSS rules and callbacks to the result details
+    fn merge(&mut self, node_details: &NodeDetails) {
+        for (css_class, (generator, style_key)) in &node_details.css {
+            if !self.details.active_css_details.contains_key(css_class) {
+                let entry = (self.clone_ref(generator), self.clone_ref(style_key));
+                self.details.active_css_details.insert(css_class.clone(), entry);
+            }
+        }
+        for (name, callback) in &node_details.callbacks {
+            let callback = self.clone_ref(callback);
+            self.details.registered_callbacks.insert(name.clone(), callback);
+        }
+    }
+
+    fn push_event_bindings(&mut self, node: &RustNodeData) {
+        if self.config.event_mode == EventMode::DataAttributes {
+            let tag = self.config.element_tag(&node.widget_type);
+            self.details.event_bindings.extend(event_bindings(&node.html_id, tag, &node.props));
+        }
+    }
+
+    /// Call `widget.get_state()` on a StatefulWidget and return the state if
+    /// it is not None
+    fn state_of(&self, node: &RustNodeData) -> PyResult<Option<Bound<'py, PyAny>>> {
+        let Some(ref instance) = node.widget_instance else {
+            return Ok(None);
+        };
+        if node.widget_type != "StatefulWidget" {
+            return Ok(None);
+        }
+        let Some(get_state) = instance.0.bind(self.py).getattr_opt("get_state")? else {
+            return Ok(None);
+        };
+        let state = get_state.call0()?;
+        Ok(if state.is_none() { None } else { Some(state) })
+    }
+
+    /// Call `state.<hook>(*args)` when the state defines it; exceptions are
+    /// returned for the engine to collect
+    fn call_state_hook(
+        &self,
+        node: &RustNodeData,
+        hook: &str,
+        args: impl PyCallArgs<'py>,
+    ) -> Result<(), ReconcilerError> {
+        if let Some(state) = self.state_of(node)?
+            && let Some(method) = state.getattr_opt(hook)?
+        {
+            method.call1(args)?;
+        }
+        Ok(())
+    }
+}
+
+/// Whether `node_details` were collected from `node`'s widget object
+fn collected_from(node_details: &NodeDetails, node: &RustNodeData) -> bool {
+    node.widget_instance.as_ref().is_some_and(|w| w.0.as_ptr() == node_details.instance.0.as_ptr())
+}
+
+impl DiffHost for PyHost<'_, '_> {
+    type Widget = PyObjectWrapper;
+    type Error = ReconcilerError;
+
+    fn generate_html(&mut self, node: &RustNodeData) -> Result<String, ReconcilerError> {
+        match node.widget_instance {
+            Some(ref w) => generate_html_stub(self.py, w.0.clone_ref(self.py), &node.html_id, &node.props, self.config, Some(&mut self.stats)),
+            None => Ok(String::new()),
+        }
+    }
+
+    /// Thread-safe details collection with explicit GIL usage
+    fn collect_details(&mut self, node: &RustNodeData) -> Result<(), ReconcilerError> {
+        let Some(ref instance) = node.widget_instance else {
+            return Ok(());
+        };
+        let mut node_details = NodeDetails { instance: self.clone_ref(instance), css: Vec::new(), callbacks: Vec::new() };
+        let instance = &instance.0;
+
+        // CSS classes
+        let css_classes = node.props.get("css_class")
+            .and_then(|v| v.as_str())
+            .unwrap_or("")
+            .split_whitespace();
+
+        for css_class in css_classes {
+            if let Some((generator, style_key)) = self.details.active_css_details.get(css_class) {
+                let entry = (self.clone_ref(generator), self.clone_ref(style_key));
+                node_details.css.push((css_class.to_string(), entry));
+                continue;
+            }
+            if let (Ok(generator), Ok(style_key)) = (
+                instance.getattr(self.py, "generate_css_rule"),
+                instance.getattr(self.py, "style_key"),
+            ) {
+                node_details.css.push((
+                    css_class.to_string(),
+                    (PyObjectWrapper(generator), PyObjectWrapper(style_key)),
+                ));
+            }
+        }
+
+        // Callbacks
+        for (prop_name, value) in &node.props {
+            if let Some(function_name) = prop_name.strip_suffix("Name") {
+                if value.is_null() {
+                    continue;
+                }
+                if let Ok(callback) = instance.getattr(self.py, function_name)
+                    && callback.bind(self.py).is_callable()
+                {
+                    node_details.callbacks.push((
+                        value.as_str().unwrap_or("").to_string(),
+                        PyObjectWrapper(callback),
+                    ));
+                }
+            }
+        }
+
+        self.merge(&node_details);
+        self.push_event_bindings(node);
+        self.cache.insert(node.html_id.clone(), node_details);
+        Ok(())
+    }
+
+    /// Replay the cached details when they came from this very widget
+    /// object; only a cache miss goes back to Python
+    fn reuse_details(&mut self, node: &RustNodeData) -> Result<(), ReconcilerError> {
+        match self.cache.remove(&node.html_id) {
+            Some(node_details) if collected_from(&node_details, node) => {
+                self.merge(&node_details);
+                self.push_event_bindings(node);
+                self.cache.insert(node.html_id.clone(), node_details);
+                Ok(())
+            }
+            _ => self.collect_details(node),
+        }
+    }
+
+    fn init_state(&mut self, node: &RustNodeData) -> Result<(), ReconcilerError> {
+        self.call_state_hook(node, "initState", ())
+    }
+
+    fn did_change_dependencies(&mut self, node: &RustNodeData) -> Result<(), ReconcilerError> {
+        self.call_state_hook(node, "didChangeDependencies", ())
+    }
+
+    fn deactivate(&mut self, node: &RustNodeData) -> Result<(), ReconcilerError> {
+        self.call_state_hook(node, "deactivate", ())
+    }
+
+    fn activate(&mut self, node: &RustNodeData) -> Result<(), ReconcilerError> {
+        self.call_state_hook(node, "activate", ())
+    }
+
+    fn did_update_widget(&mut self, old: &RustNodeData, new: &RustNodeData) -> Result<(), ReconcilerError> {
+        if new.widget_type != "StatefulWidget" {
+            return Ok(());
+        }
+        let old_props_py = json_to_pyobject(self.py, &serde_json::Value::Object(map_to_json_value(&old.props)))?;
+        self.call_state_hook(new, "didUpdateWidget", (old_props_py,))
+    }
+
+    fn dispose(&mut self, node: &RustNodeData) -> Result<(), ReconcilerError> {
+        // A replacement may already have collected under the same html_id
+        if self.cache.get(&node.html_id).is_some_and(|d| collected_from(d, node)) {
+            self.cache.remove(&node.html_id);
+        }
+        self.call_state_hook(node, "dispose", ())
+    }
+}
@@ -1,9 +1,16 @@
 //! Complete HTML generation with consistent escaping and zero panics
 use crate::errors::ReconcilerError;
+use crate::templates::WidgetTemplate;
+use log::warn;
+use crate::types::PyStats;
 use super::converters::json_to_pyobject;
 use pyo3::prelude::*;
-use pyo3::types::{PyString, PyList};
+use pyo3::types::{PyDict, PyList, PyString};
+use reconciler_core::events::{EventMode, event_bindings, push_event_attrs};
+use reconciler_core::html::{self, Element, HtmlNode};
+use reconciler_core::markup::{css_escape_value, html_escape, is_event_handler_attribute, is_valid_attribute_name, is_valid_css_property};
 use std::collections::HashMap;
+use std::time::Instant;
 use phf::phf_map;
 
 // Compile-time widget tag lookup (zero allocation)
@@ -26,21 +33,93 @@ static WIDGET_TAGS: phf::Map<&'static str, &'static str> = phf_map! {
     "Positioned" => "div",
 };
 
-/// Consistent HTML attribute escaping
-fn html_escape(s: &str) -> String {
-    s.replace('&', "&amp;")
-        .replace('<', "&lt;")
-        .replace('>', "&gt;")
-        .replace('"', "&quot;")
-        .replace('\'', "&#x27;")
+/// A tag registered at runtime with `Reconciler.register_widget_tag`
+#[derive(Debug, Clone)]
+pub struct TagSpec {
+    pub tag: String,
+    pub void: bool,
 }
 
-/// Generate HTML stub with comprehensive error handling
+/// Per-reconciler stub generation settings, layered over the static defaults
+#[derive(Debug, Clone, Default)]
+pub struct StubConfig {
+    pub tags: HashMap<String, TagSpec>,
+    /// Templates replace both the tag lookup and the widget-specific branch
+    pub templates: HashMap<String, WidgetTemplate>,
+    pub event_mode: EventMode,
+}
+
+impl StubConfig {
+    /// Tag the generic stub generator uses for a widget type
+    pub fn element_tag(&self, widget_type: &str) -> &str {
+        match self.templates.get(widget_type) {
+            Some(template) => &template.tag,
+            None => self.tag_for(widget_type).0,
+        }
+    }
+
+    /// Tag for a widget type and whether it is a void element
+    fn tag_for(&self, widget_type: &str) -> (&str, bool) {
+        if let Some(spec) = self.tags.get(widget_type) {
+            return (&spec.tag, spec.void);
+        }
+        let tag = WIDGET_TAGS.get(widget_type).copied().unwrap_or("div");
+        (tag, html::is_void_element(tag))
+    }
+}
+
+/// Append one attribute from prop data. Invalid names and `on*` handlers
+/// are skipped; `true` renders a bare boolean attribute (`disabled`) and
+/// `false`/null leave the attribute out.
+fn push_attr(attrs: &mut String, name: &str, value: &serde_json::Value) {
+    if !is_valid_attribute_name(name) {
+        warn!("Skipping invalid attribute name {:?}", name);
+        return;
+    }
+    if is_event_handler_attribute(name) {
+        warn!("Skipping event handler attribute '{}' from props", name);
+        return;
+    }
+    match value {
+        serde_json::Value::Bool(true) => {
+            attrs.push(' ');
+            attrs.push_str(name);
+        }
+        serde_json::Value::Bool(false) | serde_json::Value::Null => {}
+        serde_json::Value::String(s) => attrs.push_str(&format!(r#" {}="{}""#, name, html_escape(s))),
+        other => attrs.push_str(&format!(r#" {}="{}""#, name, html_escape(&other.to_string()))),
+    }
+}
+
+/// Generate HTML stub with comprehensive error handling. When `stats` is
+/// given, the time spent and any `_generate_html_stub` call are recorded.
 pub fn generate_html_stub<'py>(
     py: Python<'py>,
     widget: pyo3::Py<pyo3::PyAny>,
     html_id: &str,
     props: &HashMap<String, serde_json::Value>,
+    config: &StubConfig,
+    stats: Option<&mut PyStats>,
+) -> Result<String, ReconcilerError> {
+    let started = Instant::now();
+    let mut called_python = false;
+    let html = render_stub(py, widget, html_id, props, config, &mut called_python);
+    if let Some(stats) = stats {
+        stats.html_stub_time += started.elapsed();
+        if called_python {
+            stats.generate_html_stub_calls += 1;
+        }
+    }
+    html
+}
+
+fn render_stub<'py>(
+    py: Python<'py>,
+    widget: pyo3::Py<pyo3::PyAny>,
+    html_id: &str,
+    props: &HashMap<String, serde_json::Value>,
+    config: &StubConfig,
+    called_python: &mut bool,
 ) -> Result<String, ReconcilerError> {
     let widget_bound = widget.bind(py);
     
@@ -52,6 +131,7 @@ pub fn generate_html_stub<'py>(
     if let Ok(generator) = widget_bound.get_type().getattr("_generate_html_stub") {
         let html_id_py = PyString::new(py, html_id);
         let props_py = json_to_pyobject(py, &serde_json::Value::Object(map_to_json_value(props)))?;
+        *called_python = true;
         return generator.call1((widget_bound, html_id_py, props_py))?
             .extract::<String>()
             .map_err(|e| ReconcilerError::HtmlGenerationError {
@@ -70,7 +150,7 @@ pub fn generate_html_stub<'py>(
         return Ok(s);
     }
 
-    generate_generic_stub(py, widget, html_id, props)
+    generate_generic_stub(py, widget, html_id, props, config)
 }
 
 /// Generic HTML stub generator with all widget logic
@@ -79,6 +159,7 @@ fn generate_generic_stub<'py>(
     widget: pyo3::Py<pyo3::PyAny>,
     html_id: &str,
     props: &HashMap<String, serde_json::Value>,
+    config: &StubConfig,
 ) -> Result<String, ReconcilerError> {
     let widget_bound = widget.bind(py);
     
@@ -87,7 +168,11 @@ fn generate_generic_stub<'py>(
         Err(_) => "unknown".to_string(),
     };
     
-    let tag = WIDGET_TAGS.get(widget_type.as_str()).unwrap_or(&"div");
+    let template = config.templates.get(&widget_type);
+    let (tag, is_void_element) = match template {
+        Some(t) => (t.tag.as_str(), t.void),
+        None => config.tag_for(&widget_type),
+    };
     
     // Build classes string
     let mut classes = props.get("css_class")
@@ -96,41 +181,57 @@ fn generate_generic_stub<'py>(
         .to_string();
 
     // Add required CSS classes
-    if let Ok(method) = widget_bound.getattr("get_required_css_classes") {
-        if let Ok(additional_any) = method.call0() {
-            // FIX: Use .cast() instead of .cast_as()
-            if let Ok(list) = additional_any.cast::<PyList>() {
-                for item in list.iter() {
-                    if !classes.is_empty() {
-                        classes.push(' ');
-                    }
-                    classes.push_str(&item.extract::<String>().map_err(|e| ReconcilerError::TypeConversionError { expected: "String".into(), actual: e.to_string() })?);
-                }
+    if let Ok(method) = widget_bound.getattr("get_required_css_classes")
+        && let Ok(additional_any) = method.call0()
+        && let Ok(list) = additional_any.cast::<PyList>()
+    {
+        for item in list.iter() {
+            if !classes.is_empty() {
+                classes.push(' ');
             }
+            classes.push_str(&item.extract::<String>().map_err(|e| ReconcilerError::TypeConversionError { expected: "String".into(), actual: e.to_string() })?);
         }
     }
 
+    let classes = html_escape(&classes);
+
     // FIX: Move variable declarations BEFORE the match
     let mut attrs = String::new();
     let mut inline_styles = Vec::new();
+    let mut template_text = None;
+
+    // Handlers go on every element, including the early-return branches below
+    let mut event_attrs = String::new();
+    for binding in event_bindings(html_id, tag, props) {
+        push_event_attrs(&mut event_attrs, &binding, config.event_mode);
+    }
+
+    if let Some(template) = template {
+        for (name, value) in template.attributes(props) {
+            push_attr(&mut attrs, &name, &value);
+        }
+        inline_styles.extend(template.styles(props));
+        template_text = template.text(props);
+    }
 
     // ===== WIDGET-SPECIFIC LOGIC =====
     match widget_type.as_str() {
+        // A registered template replaces the built-in branch
+        _ if template.is_some() => {}
+
         "Icon" => {
             if let Some(icon_name) = props.get("data").and_then(|v| v.as_str()) {
-                if let Some(render_type) = props.get("render_type").and_then(|v| v.as_str()) {
-                    if render_type == "img" {
-                        let src = props.get("custom_icon_src")
-                            .and_then(|v| v.as_str())
-                            .unwrap_or("");
-                        attrs.push_str(&format!(r#" src="{}""#, html_escape(src)));
-                        return Ok(format!(r#"<img id="{}" class="{}" alt="{}">"#, 
-                            html_id, classes, html_escape(icon_name)));
-                    }
+                if props.get("render_type").and_then(|v| v.as_str()) == Some("img") {
+                    let src = props.get("custom_icon_src")
+                        .and_then(|v| v.as_str())
+                        .unwrap_or("");
+                    push_attr(&mut attrs, "src", &src.into());
+                    return Ok(format!(r#"<img id="{}" class="{}" alt="{}"{}{}>"#, 
+                        html_id, classes, html_escape(icon_name), attrs, event_attrs));
                 }
                 // Font Awesome: put the icon name as inner HTML (matches Python)
-                return Ok(format!(r#"<i id="{}" class="{}">{}</i>"#, 
-                    html_id, classes, html_escape(icon_name)));
+                return Ok(format!(r#"<{tag} id="{}" class="{}"{}>{}</{tag}>"#, 
+                    html_id, classes, event_attrs, html_escape(icon_name)));
             }
         }
         
@@ -138,29 +239,29 @@ fn generate_generic_stub<'py>(
             let text = props.get("data")
                 .and_then(|v| v.as_str())
                 .unwrap_or("");
-            return Ok(format!(r#"<p id="{}" class="{}">{}</p>"#, 
-                html_id, classes, html_escape(text)));
+            return Ok(format!(r#"<{tag} id="{}" class="{}"{}>{}</{tag}>"#, 
+                html_id, classes, event_attrs, html_escape(text)));
         }
         
         "Image" => {
-            if let Some(src) = props.get("src").and_then(|v| v.as_str()) {
-                attrs.push_str(&format!(r#" src="{}""#, html_escape(src)));
+            if let Some(src) = props.get("src").filter(|v| v.is_string()) {
+                push_attr(&mut attrs, "src", src);
             }
             attrs.push_str(r#" alt="""#);
         }
         
         "ClipPath" => {
             if let Some(width) = props.get("width").and_then(|v| v.as_str()) {
-                inline_styles.push(format!("width: {}", width));
+                inline_styles.push(("width".to_string(), width.to_string()));
             }
             if let Some(height) = props.get("height").and_then(|v| v.as_str()) {
-                inline_styles.push(format!("height: {}", height));
+                inline_styles.push(("height".to_string(), height.to_string()));
             }
             if let Some(clip_path) = props.get("clip_path_string").and_then(|v| v.as_str()) {
-                inline_styles.push(format!("clip-path: {}", clip_path));
+                inline_styles.push(("clip-path".to_string(), clip_path.to_string()));
             }
             if let Some(ratio) = props.get("aspectRatio").and_then(|v| v.as_str()) {
-                inline_styles.push(format!("aspect-ratio: {}", ratio));
+                inline_styles.push(("aspect-ratio".to_string(), ratio.to_string()));
             }
         }
         
@@ -171,7 +272,7 @@ fn generate_generic_stub<'py>(
                 } else {
                     w.as_str().unwrap_or("").to_string()
                 };
-                inline_styles.push(format!("width: {}", width));
+                inline_styles.push(("width".to_string(), width));
             }
             if let Some(h) = props.get("height") {
                 let height = if let Some(num) = h.as_f64() {
@@ -179,33 +280,33 @@ fn generate_generic_stub<'py>(
                 } else {
                     h.as_str().unwrap_or("").to_string()
                 };
-                inline_styles.push(format!("height: {}", height));
+                inline_styles.push(("height".to_string(), height));
             }
         }
         
         "Divider" => {
-            inline_styles.push("width: 100%".to_string());
+            inline_styles.push(("width".to_string(), "100%".to_string()));
             if let Some(h) = props.get("height").and_then(|v| v.as_f64()) {
-                inline_styles.push(format!("height: {}px", h));
+                inline_styles.push(("height".to_string(), format!("{}px", h)));
             }
             if let Some(color) = props.get("color").and_then(|v| v.as_str()) {
-                inline_styles.push(format!("background-color: {}", color));
+                inline_styles.push(("background-color".to_string(), color.to_string()));
             }
             if let Some(margin) = props.get("margin").and_then(|v| v.as_str()) {
-                inline_styles.push(format!("margin: {}", margin));
+                inline_styles.push(("margin".to_string(), margin.to_string()));
             }
         }
         
         "AspectRatio" => {
             if let Some(ratio) = props.get("aspectRatio").and_then(|v| v.as_str()) {
-                inline_styles.push(format!("aspect-ratio: {}", ratio));
+                inline_styles.push(("aspect-ratio".to_string(), ratio.to_string()));
             }
         }
         
         "Positioned" => {
             for prop in ["top", "bottom", "left", "right", "width", "height"] {
                 if let Some(val) = props.get(prop).and_then(|v| v.as_str()) {
-                    inline_styles.push(format!("{}: {}", prop, val));
+                    inline_styles.push((prop.to_string(), val.to_string()));
                 }
             }
         }
@@ -221,63 +322,43 @@ fn generate_generic_stub<'py>(
                 Some(s) => s.to_string(),
                 None => value.to_string(),
             };
-            inline_styles.push(format!("{}: {}", css_key, css_value));
+            inline_styles.push((css_key, css_value));
         }
     }
 
     if let Some(pos) = props.get("position_type").and_then(|v| v.as_str()) {
-        inline_styles.push(format!("position: {}", pos));
+        inline_styles.push(("position".to_string(), pos.to_string()));
     }
 
     // Build style attribute
-    if !inline_styles.is_empty() {
-        attrs.push_str(&format!(r#" style="{}""#, inline_styles.join("; ")));
+    let declarations: Vec<String> = inline_styles.iter()
+        .filter(|(property, _)| {
+            let valid = is_valid_css_property(property);
+            if !valid {
+                warn!("Skipping invalid CSS property {:?}", property);
+            }
+            valid
+        })
+        .map(|(property, value)| format!("{}: {}", property, css_escape_value(value)))
+        .collect();
+    if !declarations.is_empty() {
+        attrs.push_str(&format!(r#" style="{}""#, html_escape(&declarations.join("; "))));
     }
 
     // Generic attributes
     if let Some(attr_dict) = props.get("attributes").and_then(|v| v.as_object()) {
         for (key, value) in attr_dict {
-            attrs.push_str(&format!(
-                r#" {}="{}""#,
-                html_escape(key),
-                html_escape(value.as_str().unwrap_or(&value.to_string()))
-            ));
+            push_attr(&mut attrs, key, value);
         }
     }
 
-    // Event handlers
-    if props.get("enabled").and_then(|v| v.as_bool()).unwrap_or(true) {
-        if let Some(cb_name) = props.get("onPressedName").and_then(|v| v.as_str()) {
-            let has_args = props.get("onPressedArgs")
-                .and_then(|v| v.as_array())
-                .map(|arr| !arr.is_empty())
-                .unwrap_or(false);
-            
-            if has_args {
-                let args = props.get("onPressedArgs").unwrap();
-                attrs.push_str(&format!(
-                    r#" onclick="handleClickWithArgs('{}', '{}')""#,
-                    html_escape(cb_name),
-                    html_escape(&serde_json::to_string(args).unwrap_or_else(|_| "[]".to_string()))
-                ));
-            } else {
-                attrs.push_str(&format!(
-                    r#" onclick="handleClick('{}')""#,
-                    html_escape(cb_name)
-                ));
-            }
-        }
-    }
+    attrs.push_str(&event_attrs);
 
     // Tooltip
-    if let Some(tooltip) = props.get("tooltip").and_then(|v| v.as_str()) {
-        attrs.push_str(&format!(
-            r#" title="{}""#,
-            html_escape(tooltip)
-        ));
+    if let Some(tooltip) = props.get("tooltip").filter(|v| v.is_string()) {
+        push_attr(&mut attrs, "title", tooltip);
     }
 
-    let is_void_element = ["img", "hr", "br"].contains(&tag);
     if is_void_element {
         Ok(format!(r#"<{tag} id="{id}" class="{classes}"{attrs}>"#,
             tag = tag,
@@ -286,8 +367,8 @@ fn generate_generic_stub<'py>(
             attrs = attrs
         ))
     } else {
-        let inner_html = props.get("inner_html")
-            .and_then(|v| v.as_str())
+        let inner_html = template_text.as_deref()
+            .or_else(|| props.get("inner_html").and_then(|v| v.as_str()))
             .unwrap_or("");
         Ok(format!(r#"<{tag} id="{id}" class="{classes}"{attrs}>{inner}</{tag}>"#,
             tag = tag,
@@ -299,6 +380,48 @@ fn generate_generic_stub<'py>(
     }
 }
 
+/// Parse one stub back into its parts, e.g. `{"tag": "p", "id": "fw_id_1",
+/// "classes": [...], "style": {...}, "attributes": {...}, "text": "...",
+/// "void": False, "children": [...]}`. `attributes` holds everything except
+/// id, class and style; `children` holds nested elements in the same form.
+#[pyfunction]
+pub fn parse_stub<'py>(py: Python<'py>, html: &str) -> PyResult<Bound<'py, PyDict>> {
+    let element = html::parse_single_element(html).map_err(ReconcilerError::from)?;
+    element_to_dict(py, &element)
+}
+
+fn element_to_dict<'py>(py: Python<'py>, element: &Element) -> PyResult<Bound<'py, PyDict>> {
+    let dict = PyDict::new(py);
+    dict.set_item("tag", &element.tag.name)?;
+    dict.set_item("id", element.attr("id"))?;
+    dict.set_item("classes", element.classes())?;
+
+    let style = PyDict::new(py);
+    for (property, value) in element.style() {
+        style.set_item(property, value)?;
+    }
+    dict.set_item("style", style)?;
+
+    let attributes = PyDict::new(py);
+    for (name, value) in &element.tag.attrs {
+        if !matches!(name.as_str(), "id" | "class" | "style") {
+            attributes.set_item(name, value)?;
+        }
+    }
+    dict.set_item("attributes", attributes)?;
+    dict.set_item("text", element.text())?;
+    dict.set_item("void", html::is_void_element(&element.tag.name))?;
+
+    let children = PyList::empty(py);
+    for child in &element.children {
+        if let HtmlNode::Element(child) = child {
+            children.append(element_to_dict(py, child)?)?;
+        }
+    }
+    dict.set_item("children", children)?;
+    Ok(dict)
+}
+
 pub(crate) fn map_to_json_value(map: &HashMap<String, serde_json::Value>) -> serde_json::Map<String, serde_json::Value> {
         map.iter().map(|(k, v)| (k.clone(), v.clone())).collect()
 }
\ No newline at end of file
@@ -1,65 +1,244 @@
 //! Python module entry point with GIL-safe operations
 mod converters;
-mod diff_engine;
 mod errors;
+mod host;
 mod html_generator;
+mod logging;
+mod render_stream;
+mod results;
+mod templates;
+mod tree_builder;
 mod types;
 
 use crate::errors::ReconcilerError;
-use crate::html_generator::{generate_html_stub as rust_generate_html_stub, map_to_json_value};
-use converters::{json_to_pyobject, py_dict_to_rust_map};
-use diff_engine::DiffEngine;
+use crate::html_generator::{StubConfig, TagSpec, generate_html_stub as rust_generate_html_stub, map_to_json_value};
+use crate::templates::WidgetTemplate;
+use converters::{json_to_pyobject, py_dict_to_rust_map, python_to_json};
+use host::PyHost;
+use log::{debug, info, trace, warn};
 use pyo3::exceptions::PyValueError;
 use pyo3::prelude::*;
-use pyo3::types::{PyDict, PyList}; // REMOVED unused PyTuple
-use std::collections::{HashMap, HashSet};
-use std::sync::{Arc, Mutex}; // REMOVED unused atomic imports
-use types::{PatchAction, RustNodeData, RustPatch, RustReconciliationResult}; // REMOVED JsInitializer
+use pyo3::types::{PyDict, PyList};
+use reconciler_core::html::{StartTag, scan_start_tags};
+use reconciler_core::events::EventMode;
+use reconciler_core::markup::is_valid_tag_name;
+use reconciler_core::{DEFAULT_ID_PREFIX, DiffOptions, IdGenerator, IdStrategy, PropComparator, PropDiffRules, is_valid_id_prefix};
+use render_stream::PyRenderStream;
+use results::{PyJsInitializer, PyPatch, PyReconcileStats, PyReconciliationResult, PyRenderResult};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use tree_builder::{BuildOptions, TreeBuilder};
+use types::{DetailsCache, NodeMap, PyDetails, PyObjectWrapper, PyStats, RustNodeData};
+
+type CoreResult = reconciler_core::RustReconciliationResult<PyObjectWrapper>;
 
 #[pyclass]
 pub struct Reconciler {
-    context_maps: Arc<Mutex<HashMap<String, HashMap<String, RustNodeData>>>>,
+    context_maps: Arc<Mutex<HashMap<String, NodeMap>>>,
+    /// Rename duplicate keys to `<parent key>/<key>#<sibling index>` instead
+    /// of failing with `DuplicateKey`
+    #[pyo3(get, set)]
+    auto_disambiguate_keys: bool,
+    /// Reuse unchanged subtrees (by `should_update` or `memo_key`) from the
+    /// previous tree without rebuilding or diffing them
+    #[pyo3(get, set)]
+    memoize: bool,
+    ids: IdGenerator,
+    stub_config: Arc<Mutex<StubConfig>>,
+    prop_rules: Arc<Mutex<PropDiffRules>>,
+    /// CSS rules and callbacks per rendered node, replayed for the unchanged
+    /// nodes of memoized subtrees instead of asking Python again
+    details_cache: Arc<Mutex<DetailsCache>>,
 }
 
 #[pymethods]
 impl Reconciler {
     #[new]
-    fn new() -> Self {
-        println!("🪄  PyThra Framework | Reconciler Initialized (Rust)");
+    #[pyo3(signature = (auto_disambiguate_keys=false, id_strategy="global", id_prefix=DEFAULT_ID_PREFIX, event_mode="inline", memoize=false))]
+    fn new(auto_disambiguate_keys: bool, id_strategy: &str, id_prefix: &str, event_mode: &str, memoize: bool) -> PyResult<Self> {
+        let id_strategy: IdStrategy = id_strategy.parse().map_err(ReconcilerError::from)?;
+        if !is_valid_id_prefix(id_prefix) {
+            return Err(PyValueError::new_err(format!("Invalid id_prefix '{}'", id_prefix)));
+        }
+        let event_mode = parse_event_mode(event_mode)?;
+        info!("🪄  PyThra Framework | Reconciler Initialized (Rust)");
 
         let mut context_maps = HashMap::new();
         context_maps.insert("main".to_string(), HashMap::new());
 
-        Reconciler {
+        Ok(Reconciler {
             context_maps: Arc::new(Mutex::new(context_maps)),
+            auto_disambiguate_keys,
+            memoize,
+            ids: IdGenerator::new(id_strategy, id_prefix),
+            stub_config: Arc::new(Mutex::new(StubConfig { event_mode, ..StubConfig::default() })),
+            prop_rules: Arc::new(Mutex::new(PropDiffRules::default())),
+            details_cache: Arc::new(Mutex::new(DetailsCache::new())),
+        })
+    }
+
+    /// "global", "counter" or "key_path"
+    #[getter]
+    fn id_strategy(&self) -> &'static str {
+        match self.ids.strategy() {
+            IdStrategy::Global => "global",
+            IdStrategy::Counter => "counter",
+            IdStrategy::KeyPath => "key_path",
+        }
+    }
+
+    #[getter]
+    fn id_prefix(&self) -> &str {
+        self.ids.prefix()
+    }
+
+    /// "inline" (`onclick`-style attributes) or "data_attributes" (`data-fw-on-*`
+    /// attributes plus `event_bindings` in results, for a strict CSP)
+    #[getter]
+    fn event_mode(&self) -> &'static str {
+        self.stub_config.lock().unwrap().event_mode.name()
+    }
+
+    #[setter]
+    fn set_event_mode(&self, event_mode: &str) -> PyResult<()> {
+        self.stub_config.lock().unwrap().event_mode = parse_event_mode(event_mode)?;
+        Ok(())
+    }
+
+    /// Restart the "counter" id strategy so the next render mints the same
+    /// ids as the first one did
+    fn reset_ids(&self) {
+        self.ids.reset();
+    }
+
+    /// Map a widget type to an HTML tag for the generic stub generator,
+    /// overriding the built-in table. `void` elements get no closing tag.
+    #[pyo3(signature = (type_name, tag, void=false))]
+    fn register_widget_tag(&self, type_name: String, tag: &str, void: bool) -> PyResult<()> {
+        if !is_valid_tag_name(tag) {
+            return Err(PyValueError::new_err(format!("Invalid HTML tag name '{}'", tag)));
+        }
+        let spec = TagSpec { tag: tag.to_ascii_lowercase(), void };
+        self.stub_config.lock().unwrap().tags.insert(type_name, spec);
+        Ok(())
+    }
+
+    /// Register a declarative stub template for a widget type (format in
+    /// the `templates` module docs). It takes precedence over both the
+    /// built-in branches and `register_widget_tag`.
+    fn register_widget_template(&self, py: Python<'_>, type_name: String, template: &Bound<'_, PyDict>) -> PyResult<()> {
+        let template = WidgetTemplate::from_json(python_to_json(py, template.as_any())?)?;
+        self.stub_config.lock().unwrap().templates.insert(type_name, template);
+        Ok(())
+    }
+
+    /// Props never compared for UPDATE patches. Without `widget_type` this
+    /// replaces the list shared by all widgets (which starts with callables
+    /// like onPressed); with it, the extra props ignored for that type.
+    #[pyo3(signature = (props, widget_type=None))]
+    fn set_ignored_props(&self, props: Vec<String>, widget_type: Option<String>) {
+        let mut rules = self.prop_rules.lock().unwrap();
+        let props = props.into_iter().collect();
+        match widget_type {
+            Some(widget_type) => {
+                rules.ignored_by_type.insert(widget_type, props);
+            }
+            None => rules.ignored = props,
         }
     }
 
+    /// Sorted list set by `set_ignored_props` for `widget_type`, or the
+    /// shared one
+    #[pyo3(signature = (widget_type=None))]
+    fn ignored_props(&self, widget_type: Option<&str>) -> Vec<String> {
+        let rules = self.prop_rules.lock().unwrap();
+        let props = match widget_type {
+            Some(widget_type) => rules.ignored_by_type.get(widget_type),
+            None => Some(&rules.ignored),
+        };
+        let mut props: Vec<String> = props.into_iter().flatten().cloned().collect();
+        props.sort();
+        props
+    }
+
+    /// How `prop` is compared for UPDATE patches: "exact", "epsilon" (numbers
+    /// within `epsilon`), "unordered" (class lists in any order),
+    /// "ignore_nulls" (deep equality with null fields treated as absent), a
+    /// callable `(old, new) -> bool`, or None to drop the comparator.
+    /// `widget_type` limits it to one type and wins over the shared one.
+    #[pyo3(signature = (prop, comparator, widget_type=None, epsilon=1e-9))]
+    fn set_prop_comparator(
+        &self,
+        prop: String,
+        comparator: &Bound<'_, PyAny>,
+        widget_type: Option<String>,
+        epsilon: f64,
+    ) -> PyResult<()> {
+        let comparator = if comparator.is_none() {
+            None
+        } else if let Ok(name) = comparator.extract::<&str>() {
+            Some(match name {
+                "exact" => PropComparator::Exact,
+                "epsilon" => PropComparator::Epsilon(epsilon),
+                "unordered" => PropComparator::Unordered,
+                "ignore_nulls" => PropComparator::IgnoreNulls,
+                _ => return Err(PyValueError::new_err(format!(
+                    "Unknown comparator '{}' (expected 'exact', 'epsilon', 'unordered' or 'ignore_nulls')", name
+                ))),
+            })
+        } else if comparator.is_callable() {
+            Some(python_comparator(prop.clone(), comparator.clone().unbind()))
+        } else {
+            return Err(PyValueError::new_err("comparator must be a name, a callable or None"));
+        };
+
+        let mut rules = self.prop_rules.lock().unwrap();
+        let comparators = match widget_type {
+            Some(widget_type) => rules.comparators_by_type.entry(widget_type).or_default(),
+            None => &mut rules.comparators,
+        };
+        match comparator {
+            Some(comparator) => comparators.insert(prop, comparator),
+            None => comparators.remove(&prop),
+        };
+        Ok(())
+    }
+
     fn clear_context(&self, context_key: String) {
         let mut maps = self.context_maps.lock().unwrap();
-        maps.remove(&context_key);
+        if let Some(map) = maps.remove(&context_key) {
+            let mut cache = self.details_cache.lock().unwrap();
+            for node in map.values() {
+                cache.remove(&node.html_id);
+            }
+        }
     }
 
     fn clear_all_contexts(&self) {
         let mut maps = self.context_maps.lock().unwrap();
         maps.clear();
         maps.insert("main".to_string(), HashMap::new());
-        println!("Reconciler: Clearing all contexts.");
+        self.details_cache.lock().unwrap().clear();
+        debug!("Clearing all contexts.");
     }
 
-    #[pyo3(signature = (previous_map, new_widget_root, parent_html_id, is_partial_reconciliation=false, old_root_key=None))]
-    fn reconcile<'py>(
+    #[allow(clippy::too_many_arguments)]
+    #[pyo3(signature = (previous_map, new_widget_root, parent_html_id, is_partial_reconciliation=false, old_root_key=None, full_props=false, collect_stats=false))]
+    fn reconcile(
         &self,
-        py: Python<'py>,
+        py: Python<'_>,
         previous_map: Py<PyDict>,
         new_widget_root: Option<Py<PyAny>>,
         parent_html_id: String,
         is_partial_reconciliation: bool,
         old_root_key: Option<String>,
-    ) -> PyResult<Bound<'py, PyAny>> {
+        full_props: bool,
+        collect_stats: bool,
+    ) -> PyResult<PyReconciliationResult> {
         // FIX: Bind Py<PyDict> to get &Bound<PyDict>
         let previous_map_bound = previous_map.bind(py);
-        println!("Reconciler: Starting reconciliation. Previous map size: {}, New widget root: {}, Parent HTML ID: '{}', Partial: {}, Old root key: {:?}",
+        debug!("Starting reconciliation. Previous map size: {}, New widget root: {}, Parent HTML ID: '{}', Partial: {}, Old root key: {:?}",
             previous_map_bound.len(),
             if new_widget_root.is_some() { "Some" } else { "None" },
             parent_html_id,
@@ -71,93 +250,155 @@ impl Reconciler {
             .build_rust_node_map(py, previous_map_bound)
             .map_err(|e| PyValueError::new_err(format!("Failed to parse previous_map: {}", e)))?;
 
-        // Build new tree map
-        let mut new_map = HashMap::new();
-        if let Some(root) = new_widget_root {
-            // FIX: Bind Py<PyAny> to get &Bound<PyAny>
-            let root_bound = root.bind(py);
-            self.build_new_tree_map(py, root_bound, &parent_html_id, None, &mut new_map)?;
-            println!("Reconciler: Built new_map with {} entries.", new_map.len());
-            for k in new_map.keys() {
-                println!("Reconciler: new_map key => {}", k);
-            }
-        }
-
-        let mut rust_result = RustReconciliationResult::default();
-
-        // Determine root key. Prefer an explicit old_root_key. If missing,
-        // try to find a root in the old map. If still not found (initial
-        // render), fall back to discovering a root in the newly-built map so
-        // the diff engine can start from the actual new root widget. Only
-        // default to the literal "root" as a last resort.
-        let root_key = old_root_key
-            .or_else(|| {
-                old_map
-                    .iter()
-                    .find(|(_, data)| {
-                        data.parent_html_id == parent_html_id && data.parent_key.is_none()
-                    })
-                    .map(|(k, _)| k.clone())
-            })
-            .or_else(|| {
-                // If nothing in the old map, try to find the root in the new map
-                new_map
-                    .iter()
-                    .find(|(_, data)| {
-                        data.parent_html_id == parent_html_id && data.parent_key.is_none()
-                    })
-                    .map(|(k, _)| k.clone())
-            })
-            .unwrap_or_else(|| "root".to_string());
+        let config = self.stub_config();
+        let mut stats = PyStats::default();
+        let new_map = TreeBuilder::new(py, &old_map, &self.ids, &config, self.build_options(), &mut stats)
+            .build(new_widget_root.as_ref().map(|root| root.bind(py)), &parent_html_id)?;
+
+        let mut host = self.host(py, &config, stats);
+        let rust_result = reconciler_core::reconcile(
+            &mut host,
+            &old_map,
+            &new_map,
+            &parent_html_id,
+            is_partial_reconciliation,
+            old_root_key,
+            self.diff_options(full_props, collect_stats),
+        );
+        self.keep_details(&mut host);
+        let rust_result = rust_result?;
 
-        // Run diff engine
-        let mut engine = DiffEngine::new(py, &old_map, &new_map, &mut rust_result);
-        engine.reconcile(Some(&root_key))?;
+        // Return the typed Python result for the reconciliation
+        self.rust_result_to_python(py, rust_result, host.details, host.stats)
+    }
 
-        // DEBUG: Log chosen root key and map sizes so we can trace why
-        // the diff engine may produce no patches during initial render.
-        println!(
-            "Reconciler: chosen root_key='{}' | old_map size={} | new_map size={}",
-            root_key,
+    /// Reconcile against the map this reconciler kept from the previous call
+    /// with the same `context_key`, then remember the new rendered map. The
+    /// old tree never has to round-trip through Python.
+    #[pyo3(signature = (context_key, new_widget_root, parent_html_id, full_props=false, collect_stats=false))]
+    fn reconcile_in_context(
+        &self,
+        py: Python<'_>,
+        context_key: String,
+        new_widget_root: Option<Py<PyAny>>,
+        parent_html_id: String,
+        full_props: bool,
+        collect_stats: bool,
+    ) -> PyResult<PyReconciliationResult> {
+        // Take the old map out so the lock is not held while calling back
+        // into Python (which may itself touch this reconciler).
+        let old_map = self.context_maps.lock().unwrap()
+            .remove(&context_key)
+            .unwrap_or_default();
+        debug!("Starting reconciliation in context '{}'. Previous map size: {}, Parent HTML ID: '{}'",
+            context_key,
             old_map.len(),
-            new_map.len()
+            parent_html_id,
         );
 
-        // Handle removals for non-partial reconciliation
-        if !is_partial_reconciliation {
-            let old_keys: HashSet<_> = old_map.keys().collect();
-            let new_keys: HashSet<_> = new_map.keys().collect();
-            let removed: Vec<_> = old_keys.difference(&new_keys).cloned().collect();
-
-            for key in removed {
-                if let Some(data) = old_map.get(key) {
-                    // FIX: key is already &String
-                    // Dispose stateful widgets
-                    if data.widget_type == "StatefulWidget" {
-                        if let Some(ref instance) = data.widget_instance {
-                            // FIX: Use Python::attach instead of with_gil
-                            Python::attach(|py| {
-                                if let Ok(state) = instance.getattr(py, "get_state")?.call0(py) {
-                                    if !state.is_none(py) {
-                                        let _ = state.getattr(py, "dispose")?.call0(py);
-                                    }
-                                }
-                                Ok::<(), PyErr>(())
-                            })?;
-                        }
-                    }
-                    rust_result.patches.push(RustPatch {
-                        action: PatchAction::Remove,
-                        html_id: data.html_id.clone(),
-                        data: serde_json::Value::Null,
-                    });
-                }
+        let config = self.stub_config();
+        let diffed = (|| -> PyResult<_> {
+            let mut stats = PyStats::default();
+            let new_map = TreeBuilder::new(py, &old_map, &self.ids, &config, self.build_options(), &mut stats)
+                .build(new_widget_root.as_ref().map(|root| root.bind(py)), &parent_html_id)?;
+
+            let mut host = self.host(py, &config, stats);
+            let rust_result = reconciler_core::reconcile(
+                &mut host,
+                &old_map,
+                &new_map,
+                &parent_html_id,
+                false,
+                None,
+                self.diff_options(full_props, collect_stats),
+            );
+            self.keep_details(&mut host);
+            Ok((rust_result?, host.details, host.stats))
+        })();
+        let (rust_result, details, stats) = match diffed {
+            Ok(diffed) => diffed,
+            Err(e) => {
+                // The DOM still shows the old tree; the next frame must diff
+                // against it rather than re-insert the whole page
+                self.context_maps.lock().unwrap().insert(context_key, old_map);
+                return Err(e);
             }
-        }
+        };
+
+        self.context_maps.lock().unwrap()
+            .insert(context_key, rust_result.new_rendered_map.clone());
+
+        self.rust_result_to_python(py, rust_result, details, stats)
+    }
+
+    /// Render the whole tree for a first paint: every child's stub nested in
+    /// its nearest renderable ancestor. With `context_key`, the rendered map
+    /// is kept so a later `reconcile_in_context` diffs against this render.
+    #[pyo3(signature = (root_widget, parent_html_id, context_key=None))]
+    fn render_to_string(
+        &self,
+        py: Python<'_>,
+        root_widget: Py<PyAny>,
+        parent_html_id: String,
+        context_key: Option<String>,
+    ) -> PyResult<PyRenderResult> {
+        let old_map = NodeMap::new();
+        let config = self.stub_config();
+        let mut stats = PyStats::default();
+        let new_map = TreeBuilder::new(py, &old_map, &self.ids, &config, self.build_options(), &mut stats)
+            .build(Some(root_widget.bind(py)), &parent_html_id)?;
+        let root_key = reconciler_core::find_root_key(&old_map, &new_map, &parent_html_id, None);
+
+        let (rust_result, details, stats) = self.mount(py, &config, &new_map, &parent_html_id, stats, context_key)?;
+        let html = reconciler_core::render_to_string(&rust_result.new_rendered_map, &root_key);
+
+        let result = self.rust_result_to_python(py, rust_result, details, stats)?;
+        Ok(PyRenderResult::new(html, result))
+    }
+
+    /// Start from server-rendered markup: widgets adopt the generated ids
+    /// found in `html` in document order, so no INSERT patches are produced.
+    /// Structural mismatches are logged as warnings.
+    #[pyo3(signature = (html, root_widget, parent_html_id, context_key=None))]
+    fn hydrate(
+        &self,
+        py: Python<'_>,
+        html: &str,
+        root_widget: Py<PyAny>,
+        parent_html_id: String,
+        context_key: Option<String>,
+    ) -> PyResult<PyReconciliationResult> {
+        let elements: Vec<StartTag> = scan_start_tags(html).into_iter()
+            .filter(|e| e.attr("id").is_some_and(|id| self.ids.is_generated(id)))
+            .collect();
+        debug!("hydrate: {} server-rendered elements", elements.len());
+
+        let old_map = NodeMap::new();
+        let config = self.stub_config();
+        let mut stats = PyStats::default();
+        let new_map = TreeBuilder::new(py, &old_map, &self.ids, &config, self.build_options(), &mut stats)
+            .adopting(elements)
+            .build(Some(root_widget.bind(py)), &parent_html_id)?;
+
+        // Its INSERTs describe elements the page already has
+        let (mut rust_result, details, stats) = self.mount(py, &config, &new_map, &parent_html_id, stats, context_key)?;
+        rust_result.patches.clear();
+
+        self.rust_result_to_python(py, rust_result, details, stats)
+    }
 
-        // Return the serialized python result for the reconciliation
-        // (the last expression is returned to Python as PyResult<Bound<PyAny>>)
-        self.rust_result_to_python(py, rust_result)
+    /// Like `render_to_string`, but as an iterator of HTML chunks of at
+    /// least `chunk_size` bytes, building the tree as it goes
+    #[pyo3(signature = (root_widget, parent_html_id, chunk_size=8192, context_key=None))]
+    fn render_stream(
+        slf: PyRef<'_, Self>,
+        root_widget: Py<PyAny>,
+        parent_html_id: String,
+        chunk_size: usize,
+        context_key: Option<String>,
+    ) -> PyRenderStream {
+        let config = slf.stub_config();
+        PyRenderStream::new(slf.into(), config, root_widget, parent_html_id, chunk_size, context_key)
     }
 
     /// Expose a Rust-backed HTML stub generator as a method on the Reconciler pyclass.
@@ -173,11 +414,11 @@ impl Reconciler {
     ) -> PyResult<String> {
         // Convert incoming props (a Python dict) into Rust serde_json map
         let props_bound = props.bind(py);
-        let props_map = py_dict_to_rust_map(py, &props_bound)
+        let props_map = py_dict_to_rust_map(py, props_bound)
             .map_err(|e| PyValueError::new_err(format!("Failed to convert props: {}", e)))?;
 
         // Delegate to the common Rust HTML generator
-        rust_generate_html_stub(py, widget, &html_id, &props_map)
+        rust_generate_html_stub(py, widget, &html_id, &props_map, &self.stub_config(), None)
             .map_err(|e| PyValueError::new_err(e.to_string()))
     }
 }
@@ -188,25 +429,19 @@ impl Reconciler {
         &self,
         py: Python<'py>,
         py_dict: &Bound<'py, PyDict>,
-    ) -> Result<HashMap<String, RustNodeData>, ReconcilerError> {
-        println!("Reconciler: Building Rust node map from Python dict.");
+    ) -> Result<NodeMap, ReconcilerError> {
         let mut map = HashMap::new();
 
-        // FIX: Use iter() instead of items() - PyO3 0.27+ uses iter()
-        println!(
+        debug!(
             "Building Rust node map from Python dict with {} items.",
             py_dict.len()
         );
         for item_result in py_dict.iter() {
-            println!(
-                "Building Rust node map from Python dict with {} items.",
-                py_dict.len()
-            );
             let (key_obj, value) = item_result; // FIX: iter() returns tuples, not Results
                                                 // Keys in the Python `previous_map` may be plain strings or `Key` objects.
                                                 // Try extracting a String directly, otherwise attempt to call the
                                                 // widget's `__str_key__` helper or fall back to Python `str()`.
-            println!("Processing key object: {:?}", key_obj);
+            trace!("Processing key object: {:?}", key_obj);
             let key_str: String = match key_obj.extract::<String>() {
                 Ok(s) => s,
                 Err(_) => {
@@ -228,7 +463,7 @@ impl Reconciler {
                     }
                 }
             };
-            println!("Resolved key string: {}", key_str);
+            trace!("Resolved key string: {}", key_str);
 
             let data_dict = value
                 // FIX: Use cast instead of deprecated downcast
@@ -239,7 +474,7 @@ impl Reconciler {
 
             // extract widget_instance
             let widget_instance = match data_dict.get_item("widget_instance") {
-                Ok(Some(v)) => Some(v.clone().into()),
+                Ok(Some(v)) => Some(PyObjectWrapper(v.unbind())),
                 Ok(None) => None,
                 Err(e) => return Err(ReconcilerError::PythonError(e.to_string())),
             };
@@ -278,6 +513,9 @@ impl Reconciler {
                 parent_html_id: crate::safe_get!(data_dict, "parent_html_id", String),
                 parent_key,
                 children_keys: crate::safe_get!(data_dict, "children_keys", Vec<String>),
+                memo_key: crate::safe_get!(data_dict, "memo_key", Option<String>, None),
+                memoized: false,
+                subtree_hash: crate::safe_get!(data_dict, "subtree_hash", Option<u64>, None),
             };
 
             map.insert(key_str, node);
@@ -286,164 +524,87 @@ impl Reconciler {
         Ok(map)
     }
 
-    fn build_new_tree_map<'py>(
-        &self,
-        py: Python<'py>,
-        // FIX: Use &Bound<PyAny> instead of &PyAny
-        widget: &Bound<'py, PyAny>,
-        parent_html_id: &str,
-        parent_key: Option<&str>,
-        map: &mut HashMap<String, RustNodeData>,
-    ) -> PyResult<()> {
-        // FIX: Bind and call methods on Bound, not Py<T>
-        // Safely obtain the widget's unique key as a String. The Python
-        // `get_unique_id()` may return either a plain `str` or a `Key` object.
-        // Try extracting a String directly; if that fails, try calling a
-        // `__str_key__` method on the returned object, otherwise fall back to
-        // Python's str(). This mirrors the defensive logic used when reading
-        // the previous_map so both sides agree on key stringification.
-        let widget_key_obj = widget.getattr("get_unique_id")?.call0()?;
-        let widget_key: String = match widget_key_obj.extract::<String>() {
-            Ok(s) => s,
-            Err(_) => {
-                if let Ok(m) = widget_key_obj.getattr("__str_key__") {
-                    let v = m.call0()?;
-                    v.extract::<String>()
-                        .map_err(|e| ReconcilerError::KeyError {
-                            details: format!(
-                                "get_unique_id().__str_key__ did not return a string: {}",
-                                e
-                            ),
-                        })?
-                } else {
-                    let s = widget_key_obj.str()?;
-                    s.to_str()
-                        .map(|s| s.to_string())
-                        .map_err(|e| ReconcilerError::KeyError {
-                            details: format!("Cannot convert widget key to string: {}", e),
-                        })?
-                }
-            }
-        };
-        println!("build_new_tree_map: widget key resolved = {}", widget_key);
-        let html_id = types::next_id();
-
-        // Obtain props by calling widget.render_props() on the Python side
-        let props_any = widget.getattr("render_props")?.call0()?;
-        // FIX: Use cast instead of cast_as (cast_as doesn't exist)
-        let props_dict = props_any.cast::<PyDict>().map_err(|e| {
-            PyValueError::new_err(format!("render_props did not return a dict: {}", e))
-        })?;
-        let props = py_dict_to_rust_map(py, props_dict)?;
-
-        let children_any = widget.getattr("get_children")?.call0()?;
-        // FIX: Use cast instead of cast_as (cast_as doesn't exist)
-        let children_list = children_any.cast::<PyList>().map_err(|e| {
-            PyValueError::new_err(format!("get_children did not return a list: {}", e))
-        })?;
-        let mut children_keys: Vec<String> = Vec::new();
-        for child_result in children_list.iter() {
-            // iter() yields Bound, not Result
-            let child = child_result;
-            let child_key_obj = child.getattr("get_unique_id")?.call0()?;
-            let id: String = match child_key_obj.extract::<String>() {
-                Ok(s) => s,
-                Err(_) => {
-                    if let Ok(m) = child_key_obj.getattr("__str_key__") {
-                        let v = m.call0()?;
-                        v.extract::<String>()
-                            .map_err(|e| ReconcilerError::KeyError {
-                                details: format!(
-                                    "child.get_unique_id().__str_key__ did not return a string: {}",
-                                    e
-                                ),
-                            })?
-                    } else {
-                        let s = child_key_obj.str()?;
-                        s.to_str().map(|s| s.to_string()).map_err(|e| {
-                            ReconcilerError::KeyError {
-                                details: format!("Cannot convert child key to string: {}", e),
-                            }
-                        })?
-                    }
-                }
-            };
-            children_keys.push(id);
-        }
-
-        // FIX: get_type() returns Bound<PyType>, call .name() on it
-        let widget_type = widget.get_type().name()?.to_string();
-        // FIX: widget is already Bound, clone it into Py<PyAny>
-        let widget_instance_py: Py<PyAny> = widget.clone().into();
-
-        // Generate HTML stub for the widget using Rust generator to keep parity
-    let generated_html = match rust_generate_html_stub(py, widget_instance_py.clone_ref(py), &html_id, &props) {
-            Ok(s) => s,
-            Err(e) => {
-                // Fallback to empty string on error but log for debugging
-                println!("Reconciler: warning - html generation failed for {}: {}", widget_key, e);
-                String::new()
-            }
-        };
+    /// Snapshot, so no lock is held while stub generation calls into Python
+    fn stub_config(&self) -> StubConfig {
+        self.stub_config.lock().unwrap().clone()
+    }
 
-        let node = RustNodeData {
-            html_id: html_id.clone(),
-            html: generated_html,
-            widget_type: widget_type.clone(),
-            key: widget_key.clone(),
-            widget_instance: Some(widget_instance_py), // Py<PyAny> is thread-safe
-            props,
-            parent_html_id: parent_html_id.to_string(),
-            parent_key: parent_key.map(String::from),
-            children_keys,
-        };
+    fn diff_options(&self, full_props: bool, collect_stats: bool) -> DiffOptions {
+        DiffOptions { full_props, collect_stats, prop_rules: self.prop_rules.lock().unwrap().clone() }
+    }
 
-        map.insert(widget_key.clone(), node);
+    /// Diff a freshly built tree against an empty one, which collects CSS,
+    /// callbacks and js_initializers and runs the mount hooks exactly as a
+    /// first reconcile would. With `context_key`, the rendered map is kept
+    /// for `reconcile_in_context`.
+    fn mount(
+        &self,
+        py: Python<'_>,
+        config: &StubConfig,
+        new_map: &NodeMap,
+        parent_html_id: &str,
+        stats: PyStats,
+        context_key: Option<String>,
+    ) -> PyResult<(CoreResult, PyDetails, PyStats)> {
+        let old_map = NodeMap::new();
+        let mut host = self.host(py, config, stats);
+        let rust_result = reconciler_core::reconcile(
+            &mut host,
+            &old_map,
+            new_map,
+            parent_html_id,
+            false,
+            None,
+            DiffOptions::default(),
+        );
+        self.keep_details(&mut host);
+        let rust_result = rust_result?;
 
-        // Determine whether this widget type renders a real DOM element.
-        // Treat a small set of known non-renderable/internal types as non-renderable
-        // so their children are attached to the nearest renderable ancestor.
-        let is_renderable = !(widget_type == "StatefulWidget" || widget_type == "StatelessWidget" || widget_type == "_WidgetProxy");
+        if let Some(context_key) = context_key {
+            self.context_maps.lock().unwrap()
+                .insert(context_key, rust_result.new_rendered_map.clone());
+        }
+        Ok((rust_result, host.details, host.stats))
+    }
 
-        // EXACT Python parity: children of non-renderable widgets use the parent's
-        // html id (the nearest renderable ancestor). Children of renderable
-        // widgets attach to this widget's generated html_id.
-        let child_parent_id = if is_renderable { &html_id } else { parent_html_id };
+    /// Host for one call. It holds the details cache until `keep_details`
+    /// so the lock is not held while calling back into Python.
+    fn host<'a, 'py>(&self, py: Python<'py>, config: &'a StubConfig, stats: PyStats) -> PyHost<'a, 'py> {
+        let cache = std::mem::take(&mut *self.details_cache.lock().unwrap());
+        PyHost::new(py, config, stats).with_cache(cache)
+    }
 
-        for child_item in children_list.iter() {
-            // iter() yields Bound, not Result
-            let child = child_item;
-            self.build_new_tree_map(py, &child, child_parent_id, Some(&widget_key), map)?;
-        }
+    fn keep_details(&self, host: &mut PyHost) {
+        *self.details_cache.lock().unwrap() = std::mem::take(&mut host.cache);
+    }
 
-        Ok(())
+    fn build_options(&self) -> BuildOptions {
+        BuildOptions { auto_disambiguate_keys: self.auto_disambiguate_keys, memoize: self.memoize }
     }
 
-    fn rust_result_to_python<'py>(
+    fn rust_result_to_python(
         &self,
-        py: Python<'py>,
-        rust_result: RustReconciliationResult,
-    ) -> PyResult<Bound<'py, PyAny>> {
-        let result = PyDict::new(py);
+        py: Python<'_>,
+        mut rust_result: CoreResult,
+        details: PyDetails,
+        mut stats: PyStats,
+    ) -> PyResult<PyReconciliationResult> {
+        let started = Instant::now();
+        let core_stats = rust_result.stats.take();
 
         // Convert patches
-        let patches_list = PyList::empty(py);
-        for patch in &rust_result.patches {
-            let patch_dict = PyDict::new(py);
-            patch_dict.set_item("action", patch.action.to_string())?;
-            patch_dict.set_item("html_id", patch.html_id.clone())?;
-            patch_dict.set_item("data", json_to_pyobject(py, &patch.data)?)?;
-            patches_list.append(patch_dict)?;
+        let patches = PyList::empty(py);
+        for patch in rust_result.patches {
+            patches.append(PyPatch(patch))?;
         }
-        result.set_item("patches", patches_list)?;
 
-        // DEBUG: After reconciliation, report patch/new_map counts for visibility
-        println!(
-                "Reconciler: rust_result.patches={} new_rendered_map={} js_initializers={} callbacks={}",
-                rust_result.patches.len(),
+        // After reconciliation, report patch/new_map counts for visibility
+        debug!(
+                "rust_result.patches={} new_rendered_map={} js_initializers={} callbacks={}",
+                patches.len(),
                 rust_result.new_rendered_map.len(),
                 rust_result.js_initializers.len(),
-                rust_result.registered_callbacks.len()
+                details.registered_callbacks.len()
             );
 
         // Convert new_rendered_map
@@ -456,7 +617,7 @@ impl Reconciler {
             node_dict.set_item("html", node.html)?;
             node_dict.set_item(
                 "widget_instance",
-                node.widget_instance.unwrap_or_else(|| py.None().into()),
+                node.widget_instance.map(|w| w.0).unwrap_or_else(|| py.None()),
             )?;
             node_dict.set_item(
                 "props",
@@ -468,38 +629,96 @@ impl Reconciler {
             node_dict.set_item("parent_html_id", node.parent_html_id)?;
             node_dict.set_item("parent_key", node.parent_key)?;
             node_dict.set_item("children_keys", node.children_keys)?;
+            node_dict.set_item("memo_key", node.memo_key)?;
+            node_dict.set_item("subtree_hash", node.subtree_hash)?;
             rendered_map.set_item(key, node_dict)?;
         }
-        result.set_item("new_rendered_map", rendered_map)?;
 
         // Convert active_css_details
         let css_details = PyDict::new(py);
-        for (class, (generator, style_key)) in rust_result.active_css_details {
+        for (class, (generator, style_key)) in details.active_css_details {
             css_details.set_item(class, (generator.0, style_key.0))?;
         }
-        result.set_item("active_css_details", css_details)?;
 
         // Convert registered_callbacks
         let callbacks = PyDict::new(py);
-        for (name, callback) in rust_result.registered_callbacks {
+        for (name, callback) in details.registered_callbacks {
             callbacks.set_item(name, callback.0)?;
         }
-        result.set_item("registered_callbacks", callbacks)?;
 
         // Convert js_initializers
         let initializers = PyList::empty(py);
         for init in rust_result.js_initializers {
-            initializers.append(json_to_pyobject(py, &serde_json::to_value(&init).unwrap())?)?;
+            initializers.append(PyJsInitializer(init))?;
+        }
+
+        // Convert event_bindings
+        let event_bindings = PyList::empty(py);
+        for binding in details.event_bindings {
+            let binding_dict = PyDict::new(py);
+            binding_dict.set_item("html_id", binding.html_id)?;
+            binding_dict.set_item("event", binding.event)?;
+            binding_dict.set_item("key", binding.key)?;
+            binding_dict.set_item("callback", binding.callback)?;
+            binding_dict.set_item("args", json_to_pyobject(py, &binding.args.unwrap_or_default())?)?;
+            event_bindings.append(binding_dict)?;
+        }
+
+        // Convert lifecycle_errors
+        let lifecycle_errors = PyList::empty(py);
+        for error in rust_result.lifecycle_errors {
+            let error_dict = PyDict::new(py);
+            error_dict.set_item("hook", error.hook.to_string())?;
+            error_dict.set_item("key", error.key)?;
+            error_dict.set_item("html_id", error.html_id)?;
+            error_dict.set_item("error", error.message)?;
+            lifecycle_errors.append(error_dict)?;
         }
-        result.set_item("js_initializers", initializers)?;
 
-        // FIX: Convert dict to any before returning
-        Ok(result.into_any())
+        stats.to_python_time = started.elapsed();
+        let stats = match core_stats {
+            Some(core) => Some(Py::new(py, PyReconcileStats::new(core, stats))?),
+            None => None,
+        };
+
+        Ok(PyReconciliationResult {
+            patches: patches.unbind(),
+            new_rendered_map: rendered_map.unbind(),
+            active_css_details: css_details.unbind(),
+            registered_callbacks: callbacks.unbind(),
+            js_initializers: initializers.unbind(),
+            event_bindings: event_bindings.unbind(),
+            lifecycle_errors: lifecycle_errors.unbind(),
+            stats,
+        })
     }
 }
 
+/// Comparator calling `callback(old, new)`; an exception counts as "changed"
+fn python_comparator(prop: String, callback: Py<PyAny>) -> PropComparator {
+    PropComparator::Custom(Arc::new(move |old, new| {
+        Python::attach(|py| {
+            let equal = json_to_pyobject(py, old)
+                .and_then(|old| Ok((old, json_to_pyobject(py, new)?)))
+                .and_then(|args| callback.call1(py, args)?.is_truthy(py));
+            equal.unwrap_or_else(|e| {
+                warn!("Comparator for '{}' failed: {}", prop, e);
+                false
+            })
+        })
+    }))
+}
+
+fn parse_event_mode(name: &str) -> PyResult<EventMode> {
+    EventMode::from_name(name).ok_or_else(|| {
+        PyValueError::new_err(format!("Unknown event mode '{}' (expected 'inline' or 'data_attributes')", name))
+    })
+}
+
 #[pymodule]
 fn rust_reconciler(_py: Python, m: &Bound<'_, PyModule>) -> PyResult<()> {
+    logging::install();
+
     // Expose module-level helper for HTML stub generation so Python can call
     // `rust_reconciler.generate_html_stub(widget, html_id, props)` directly.
     #[pyfunction]
@@ -510,15 +729,23 @@ fn rust_reconciler(_py: Python, m: &Bound<'_, PyModule>) -> PyResult<()> {
         props: Py<PyAny>,
     ) -> PyResult<String> {
         let props_bound = props.bind(py);
-        let props_map = py_dict_to_rust_map(py, &props_bound)
+        let props_map = py_dict_to_rust_map(py, props_bound)
             .map_err(|e| PyValueError::new_err(format!("Failed to convert props: {}", e)))?;
-        rust_generate_html_stub(py, widget, &html_id, &props_map)
+        rust_generate_html_stub(py, widget, &html_id, &props_map, &StubConfig::default(), None)
             .map_err(|e| PyValueError::new_err(e.to_string()))
     }
 
     m.add_function(wrap_pyfunction!(generate_html_stub, m)?)?;
+    m.add_function(wrap_pyfunction!(logging::set_log_level, m)?)?;
+    m.add_function(wrap_pyfunction!(html_generator::parse_stub, m)?)?;
     // FIX: m is now &Bound<PyModule>, use add_class/add functions
     m.add_class::<Reconciler>()?;
+    m.add_class::<PyPatch>()?;
+    m.add_class::<PyJsInitializer>()?;
+    m.add_class::<PyReconciliationResult>()?;
+    m.add_class::<PyReconcileStats>()?;
+    m.add_class::<PyRenderResult>()?;
+    m.add_class::<PyRenderStream>()?;
 
     // Export patch types as constants
     m.add("INSERT", "INSERT")?;
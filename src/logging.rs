@@ -0,0 +1,85 @@
+//! Bridge from the `log` facade to Python's `logging` module
+//!
+//! Records from both this crate and `reconciler_core` are forwarded to
+//! `logging.getLogger("rust_reconciler.<target>")`, so the usual Python
+//! handlers and per-logger levels apply. `set_log_level` is the cheap
+//! Rust-side gate: records above it are never formatted.
+use log::{LevelFilter, Log, Metadata, Record};
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+/// Level used until Python calls `set_log_level`
+const DEFAULT_LEVEL: LevelFilter = LevelFilter::Warn;
+
+struct PyLogBridge;
+
+/// Python `logging` level number for a Rust level (TRACE maps below DEBUG)
+fn python_level(level: log::Level) -> u8 {
+    match level {
+        log::Level::Error => 40,
+        log::Level::Warn => 30,
+        log::Level::Info => 20,
+        log::Level::Debug => 10,
+        log::Level::Trace => 5,
+    }
+}
+
+/// Python logger name for a record target, e.g.
+/// `reconciler_core::diff_engine` -> `rust_reconciler.reconciler_core.diff_engine`
+fn logger_name(target: &str) -> String {
+    let dotted = target.replace("::", ".");
+    if dotted == "rust_reconciler" || dotted.starts_with("rust_reconciler.") {
+        dotted
+    } else {
+        format!("rust_reconciler.{}", dotted)
+    }
+}
+
+impl Log for PyLogBridge {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        Python::attach(|py| {
+            let forwarded = py.import("logging")
+                .and_then(|logging| logging.call_method1("getLogger", (logger_name(record.target()),)))
+                .and_then(|logger| {
+                    logger.call_method1("log", (python_level(record.level()), record.args().to_string()))
+                });
+            // A failing handler must never break reconciliation
+            if let Err(e) = forwarded {
+                e.print(py);
+            }
+        });
+    }
+
+    fn flush(&self) {}
+}
+
+/// Register the bridge as the global logger (no-op if one is already set)
+pub fn install() {
+    if log::set_logger(&PyLogBridge).is_ok() {
+        log::set_max_level(DEFAULT_LEVEL);
+    }
+}
+
+/// Set the most verbose level the reconciler emits:
+/// "off", "error", "warn"/"warning", "info", "debug" or "trace"
+#[pyfunction]
+pub fn set_log_level(level: &str) -> PyResult<()> {
+    let filter = match level.to_ascii_lowercase().as_str() {
+        "off" | "none" => LevelFilter::Off,
+        "error" | "critical" => LevelFilter::Error,
+        "warn" | "warning" => LevelFilter::Warn,
+        "info" => LevelFilter::Info,
+        "debug" => LevelFilter::Debug,
+        "trace" => LevelFilter::Trace,
+        other => return Err(PyValueError::new_err(format!("Unknown log level '{}'", other))),
+    };
+    log::set_max_level(filter);
+    Ok(())
+}
@@ -0,0 +1,158 @@
+//! Chunked first-paint HTML (`RenderStream` in Python). The tree is built
+//! lazily while iterating, so the first chunks are ready before the rest of
+//! the widget tree has been visited. Once the last chunk is out, the tree
+//! is mounted as `render_to_string` does and `result` is set.
+use crate::Reconciler;
+use crate::html_generator::StubConfig;
+use crate::results::PyReconciliationResult;
+use crate::tree_builder::TreeBuilder;
+use crate::types::{NodeMap, PyStats};
+use log::warn;
+use pyo3::prelude::*;
+use reconciler_core::{is_renderable_type, split_stub};
+use std::mem;
+
+/// A widget waiting to be built
+struct Pending {
+    widget: Py<PyAny>,
+    parent_html_id: String,
+    parent_key: Option<String>,
+    index: usize,
+    /// False below a void element: still built, but not emitted
+    emit: bool,
+}
+
+enum Frame {
+    Build(Pending),
+    Close(String),
+}
+
+#[pyclass(name = "RenderStream", module = "rust_reconciler")]
+pub struct PyRenderStream {
+    reconciler: Py<Reconciler>,
+    stack: Vec<Frame>,
+    map: NodeMap,
+    config: StubConfig,
+    stats: PyStats,
+    chunk_size: usize,
+    parent_html_id: String,
+    context_key: Option<String>,
+    result: Option<Py<PyReconciliationResult>>,
+}
+
+impl PyRenderStream {
+    pub fn new(
+        reconciler: Py<Reconciler>,
+        config: StubConfig,
+        root_widget: Py<PyAny>,
+        parent_html_id: String,
+        chunk_size: usize,
+        context_key: Option<String>,
+    ) -> Self {
+        let root = Frame::Build(Pending { widget: root_widget, parent_html_id: parent_html_id.clone(), parent_key: None, index: 0, emit: true });
+        PyRenderStream {
+            reconciler,
+            stack: vec![root],
+            map: NodeMap::new(),
+            config,
+            stats: PyStats::default(),
+            chunk_size,
+            parent_html_id,
+            context_key,
+            result: None,
+        }
+    }
+
+    /// Build one widget, append its opening markup to `out` and queue its
+    /// children followed by its closing tag
+    fn build(&mut self, py: Python<'_>, pending: Pending, out: &mut String) -> PyResult<()> {
+        let Pending { widget, parent_html_id, parent_key, index, emit } = pending;
+        let reconciler = self.reconciler.borrow(py);
+        let old_map = NodeMap::new();
+        let mut builder = TreeBuilder::new(py, &old_map, &reconciler.ids, &self.config, reconciler.build_options(), &mut self.stats)
+            .with_map(mem::take(&mut self.map));
+        let built = builder.build_single(widget.bind(py), &parent_html_id, parent_key.as_deref(), index);
+        self.map = builder.into_map();
+        let built = built?;
+
+        let node = &self.map[&built.key];
+        let mut child_emit = emit;
+        if emit && is_renderable_type(&node.widget_type) {
+            let (open, close) = split_stub(&node.html);
+            out.push_str(open);
+            if close.is_empty() {
+                if !built.children.is_empty() {
+                    warn!(
+                        "render_stream: {} '{}' has no closing tag, dropping {} children",
+                        node.widget_type, built.key, built.children.len()
+                    );
+                }
+                child_emit = false;
+            } else {
+                self.stack.push(Frame::Close(close.to_string()));
+            }
+        }
+
+        for (index, child) in built.children.iter().enumerate().rev() {
+            self.stack.push(Frame::Build(Pending {
+                widget: child.unbind(),
+                parent_html_id: built.child_parent_id.clone(),
+                parent_key: Some(built.key.clone()),
+                index,
+                emit: child_emit,
+            }));
+        }
+        Ok(())
+    }
+
+    /// Collect details and run the mount hooks for the completed tree, and
+    /// keep its map for `reconcile_in_context` if asked to
+    fn finish(&mut self, py: Python<'_>) -> PyResult<()> {
+        if self.result.is_some() {
+            return Ok(());
+        }
+        reconciler_core::compute_subtree_hashes(&mut self.map);
+        let reconciler = self.reconciler.borrow(py);
+        let stats = mem::take(&mut self.stats);
+        let (mut rust_result, details, stats) =
+            reconciler.mount(py, &self.config, &self.map, &self.parent_html_id, stats, self.context_key.take())?;
+        // The chunks already hold every element
+        rust_result.patches.clear();
+        let result = reconciler.rust_result_to_python(py, rust_result, details, stats)?;
+        self.result = Some(Py::new(py, result)?);
+        Ok(())
+    }
+}
+
+#[pymethods]
+impl PyRenderStream {
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    /// Callbacks, CSS details, js_initializers and lifecycle errors of the
+    /// rendered tree; None until the last chunk has been returned
+    #[getter]
+    fn result(&self, py: Python<'_>) -> Option<Py<PyReconciliationResult>> {
+        self.result.as_ref().map(|result| result.clone_ref(py))
+    }
+
+    /// Next chunk of at least `chunk_size` bytes (the last one may be shorter)
+    fn __next__(&mut self, py: Python<'_>) -> PyResult<Option<String>> {
+        let mut chunk = String::new();
+        loop {
+            let Some(frame) = self.stack.pop() else {
+                self.finish(py)?;
+                break;
+            };
+            match frame {
+                Frame::Close(close) => chunk.push_str(&close),
+                Frame::Build(pending) => self.build(py, pending, &mut chunk)?,
+            }
+            if !chunk.is_empty() && chunk.len() >= self.chunk_size {
+                break;
+            }
+        }
+        Ok(if chunk.is_empty() { None } else { Some(chunk) })
+    }
+}
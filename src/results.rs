@@ -0,0 +1,368 @@
+//! Python-facing result classes with attribute access
+use crate::converters::json_to_pyobject;
+use pyo3::exceptions::PyKeyError;
+use pyo3::prelude::*;
+use pyo3::types::{PyBytes, PyDict, PyList};
+use crate::types::PyStats;
+use reconciler_core::{JsInitializer, ReconcileStats, RustPatch, patches_to_json_bytes};
+use std::collections::HashMap;
+
+/// A single DOM patch (`Patch` in Python)
+#[pyclass(frozen, eq, name = "Patch", module = "rust_reconciler")]
+#[derive(Clone, PartialEq)]
+pub struct PyPatch(pub RustPatch);
+
+#[pymethods]
+impl PyPatch {
+    #[getter]
+    fn action(&self) -> String {
+        self.0.action.to_string()
+    }
+
+    #[getter]
+    fn html_id(&self) -> &str {
+        &self.0.html_id
+    }
+
+    /// Alias of `html_id`, kept for the older `Patch.target_id` spelling
+    #[getter]
+    fn target_id(&self) -> &str {
+        &self.0.html_id
+    }
+
+    #[getter]
+    fn data<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        json_to_pyobject(py, &self.0.data)
+    }
+
+    fn to_dict<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let dict = PyDict::new(py);
+        dict.set_item("action", self.action())?;
+        dict.set_item("html_id", self.html_id())?;
+        dict.set_item("data", self.data(py)?)?;
+        Ok(dict)
+    }
+
+    /// Dict-style access so code written against the old dict result keeps working
+    fn __getitem__<'py>(&self, py: Python<'py>, key: &str) -> PyResult<Bound<'py, PyAny>> {
+        self.to_dict(py)?
+            .get_item(key)?
+            .ok_or_else(|| PyKeyError::new_err(key.to_string()))
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "Patch(action='{}', html_id='{}', data={})",
+            self.0.action, self.0.html_id, self.0.data
+        )
+    }
+}
+
+/// A client-side initializer to run after patches are applied
+#[pyclass(frozen, eq, name = "JsInitializer", module = "rust_reconciler")]
+#[derive(Clone, PartialEq)]
+pub struct PyJsInitializer(pub JsInitializer);
+
+#[pymethods]
+impl PyJsInitializer {
+    #[getter(r#type)]
+    fn init_type(&self) -> &str {
+        &self.0.init_type
+    }
+
+    #[getter]
+    fn target_id(&self) -> &str {
+        &self.0.target_id
+    }
+
+    #[getter]
+    fn data<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        json_to_pyobject(py, &self.0.data)
+    }
+
+    #[getter]
+    fn before_id(&self) -> Option<&str> {
+        self.0.before_id.as_deref()
+    }
+
+    fn to_dict<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        let value = serde_json::to_value(&self.0)
+            .map_err(crate::errors::ReconcilerError::from)?;
+        json_to_pyobject(py, &value)
+    }
+
+    fn __getitem__<'py>(&self, py: Python<'py>, key: &str) -> PyResult<Bound<'py, PyAny>> {
+        self.to_dict(py)?
+            .get_item(key)
+            .map_err(|_| PyKeyError::new_err(key.to_string()))
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "JsInitializer(type='{}', target_id='{}', data={}, before_id={:?})",
+            self.0.init_type, self.0.target_id, self.0.data, self.0.before_id
+        )
+    }
+}
+
+/// Everything one reconciliation produced (`ReconciliationResult` in Python)
+#[pyclass(frozen, name = "ReconciliationResult", module = "rust_reconciler")]
+pub struct PyReconciliationResult {
+    #[pyo3(get)]
+    pub patches: Py<PyList>,
+    #[pyo3(get)]
+    pub new_rendered_map: Py<PyDict>,
+    #[pyo3(get)]
+    pub active_css_details: Py<PyDict>,
+    #[pyo3(get)]
+    pub registered_callbacks: Py<PyDict>,
+    #[pyo3(get)]
+    pub js_initializers: Py<PyList>,
+    /// `{"html_id", "event", "callback", "args"}` dicts for every handler;
+    /// empty unless the reconciler uses `event_mode="data_attributes"`
+    #[pyo3(get)]
+    pub event_bindings: Py<PyList>,
+    /// `{"hook", "key", "html_id", "error"}` dicts for StatefulWidget hooks
+    /// that raised, in call order
+    #[pyo3(get)]
+    pub lifecycle_errors: Py<PyList>,
+    /// Only set when reconciling with `collect_stats=True`
+    #[pyo3(get)]
+    pub stats: Option<Py<PyReconcileStats>>,
+}
+
+#[pymethods]
+impl PyReconciliationResult {
+    fn to_dict<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let dict = PyDict::new(py);
+        dict.set_item("patches", self.patches.bind(py))?;
+        dict.set_item("new_rendered_map", self.new_rendered_map.bind(py))?;
+        dict.set_item("active_css_details", self.active_css_details.bind(py))?;
+        dict.set_item("registered_callbacks", self.registered_callbacks.bind(py))?;
+        dict.set_item("js_initializers", self.js_initializers.bind(py))?;
+        dict.set_item("event_bindings", self.event_bindings.bind(py))?;
+        dict.set_item("lifecycle_errors", self.lifecycle_errors.bind(py))?;
+        Ok(dict)
+    }
+
+    /// Patches and js_initializers as one UTF-8 JSON buffer, serialized
+    /// straight from the Rust values
+    fn to_json_bytes<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyBytes>> {
+        let (patches, js_initializers) = self.rust_payload(py)?;
+        let buf = patches_to_json_bytes(&patches, &js_initializers)
+            .map_err(crate::errors::ReconcilerError::from)?;
+        Ok(PyBytes::new(py, &buf))
+    }
+
+    /// MessagePack form of `to_json_bytes` (schema in reconciler-core's msgpack module)
+    #[cfg(feature = "msgpack")]
+    fn to_msgpack_bytes<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyBytes>> {
+        let (patches, js_initializers) = self.rust_payload(py)?;
+        let buf = reconciler_core::msgpack::encode_payload(&patches, &js_initializers);
+        Ok(PyBytes::new(py, &buf))
+    }
+
+    fn __getitem__<'py>(&self, py: Python<'py>, key: &str) -> PyResult<Bound<'py, PyAny>> {
+        self.to_dict(py)?
+            .get_item(key)?
+            .ok_or_else(|| PyKeyError::new_err(key.to_string()))
+    }
+
+    fn __eq__(&self, py: Python<'_>, other: &Bound<'_, PyAny>) -> PyResult<bool> {
+        let Ok(other) = other.cast::<PyReconciliationResult>() else {
+            return Ok(false);
+        };
+        let other = other.get();
+        Ok(self.patches.bind(py).eq(other.patches.bind(py))?
+            && self.new_rendered_map.bind(py).eq(other.new_rendered_map.bind(py))?
+            && self.active_css_details.bind(py).eq(other.active_css_details.bind(py))?
+            && self.registered_callbacks.bind(py).eq(other.registered_callbacks.bind(py))?
+            && self.js_initializers.bind(py).eq(other.js_initializers.bind(py))?
+            && self.event_bindings.bind(py).eq(other.event_bindings.bind(py))?
+            && self.lifecycle_errors.bind(py).eq(other.lifecycle_errors.bind(py))?)
+    }
+
+    fn __repr__(&self, py: Python<'_>) -> String {
+        format!(
+            "ReconciliationResult(patches={}, new_rendered_map={}, active_css_details={}, registered_callbacks={}, js_initializers={}, event_bindings={}, lifecycle_errors={})",
+            self.patches.bind(py).len(),
+            self.new_rendered_map.bind(py).len(),
+            self.active_css_details.bind(py).len(),
+            self.registered_callbacks.bind(py).len(),
+            self.js_initializers.bind(py).len(),
+            self.event_bindings.bind(py).len(),
+            self.lifecycle_errors.bind(py).len(),
+        )
+    }
+}
+
+impl PyReconciliationResult {
+    /// Pull the Rust patch and initializer values back out of the Python lists
+    fn rust_payload(&self, py: Python<'_>) -> PyResult<(Vec<RustPatch>, Vec<JsInitializer>)> {
+        let patches = self.patches.bind(py).iter()
+            .map(|p| Ok(p.cast::<PyPatch>()?.get().0.clone()))
+            .collect::<PyResult<Vec<_>>>()?;
+        let js_initializers = self.js_initializers.bind(py).iter()
+            .map(|i| Ok(i.cast::<PyJsInitializer>()?.get().0.clone()))
+            .collect::<PyResult<Vec<_>>>()?;
+        Ok((patches, js_initializers))
+    }
+}
+
+/// Output of `Reconciler.render_to_string` (`RenderResult` in Python)
+#[pyclass(frozen, name = "RenderResult", module = "rust_reconciler")]
+pub struct PyRenderResult {
+    /// Complete nested HTML of the tree
+    #[pyo3(get)]
+    pub html: String,
+    #[pyo3(get)]
+    pub new_rendered_map: Py<PyDict>,
+    #[pyo3(get)]
+    pub active_css_details: Py<PyDict>,
+    #[pyo3(get)]
+    pub registered_callbacks: Py<PyDict>,
+    #[pyo3(get)]
+    pub js_initializers: Py<PyList>,
+    #[pyo3(get)]
+    pub event_bindings: Py<PyList>,
+    #[pyo3(get)]
+    pub lifecycle_errors: Py<PyList>,
+}
+
+impl PyRenderResult {
+    /// Keep everything of a first-render reconciliation except its patches
+    pub fn new(html: String, result: PyReconciliationResult) -> Self {
+        PyRenderResult {
+            html,
+            new_rendered_map: result.new_rendered_map,
+            active_css_details: result.active_css_details,
+            registered_callbacks: result.registered_callbacks,
+            js_initializers: result.js_initializers,
+            event_bindings: result.event_bindings,
+            lifecycle_errors: result.lifecycle_errors,
+        }
+    }
+}
+
+#[pymethods]
+impl PyRenderResult {
+    fn to_dict<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let dict = PyDict::new(py);
+        dict.set_item("html", &self.html)?;
+        dict.set_item("new_rendered_map", self.new_rendered_map.bind(py))?;
+        dict.set_item("active_css_details", self.active_css_details.bind(py))?;
+        dict.set_item("registered_callbacks", self.registered_callbacks.bind(py))?;
+        dict.set_item("js_initializers", self.js_initializers.bind(py))?;
+        dict.set_item("event_bindings", self.event_bindings.bind(py))?;
+        dict.set_item("lifecycle_errors", self.lifecycle_errors.bind(py))?;
+        Ok(dict)
+    }
+
+    fn __getitem__<'py>(&self, py: Python<'py>, key: &str) -> PyResult<Bound<'py, PyAny>> {
+        self.to_dict(py)?
+            .get_item(key)?
+            .ok_or_else(|| PyKeyError::new_err(key.to_string()))
+    }
+
+    fn __repr__(&self, py: Python<'_>) -> String {
+        format!(
+            "RenderResult(html=<{} chars>, new_rendered_map={}, active_css_details={}, registered_callbacks={}, js_initializers={}, event_bindings={}, lifecycle_errors={})",
+            self.html.len(),
+            self.new_rendered_map.bind(py).len(),
+            self.active_css_details.bind(py).len(),
+            self.registered_callbacks.bind(py).len(),
+            self.js_initializers.bind(py).len(),
+            self.event_bindings.bind(py).len(),
+            self.lifecycle_errors.bind(py).len(),
+        )
+    }
+}
+
+/// Timings (milliseconds) and counters for one reconciliation. Timings are
+/// inclusive: `html_stub_ms` overlaps both `build_tree_ms` and `diff_ms`.
+/// `lifecycle_ms` is the time spent in lifecycle hooks after the diff.
+#[pyclass(frozen, name = "ReconcileStats", module = "rust_reconciler")]
+pub struct PyReconcileStats {
+    #[pyo3(get)]
+    pub old_node_count: usize,
+    #[pyo3(get)]
+    pub new_node_count: usize,
+    /// Patch count per action name ("INSERT", "MOVE", ...)
+    #[pyo3(get)]
+    pub patch_counts: HashMap<String, usize>,
+    #[pyo3(get)]
+    pub lis_lengths: Vec<usize>,
+    #[pyo3(get)]
+    pub build_tree_ms: f64,
+    #[pyo3(get)]
+    pub diff_ms: f64,
+    #[pyo3(get)]
+    pub lifecycle_ms: f64,
+    #[pyo3(get)]
+    pub html_stub_ms: f64,
+    #[pyo3(get)]
+    pub to_python_ms: f64,
+    /// Calls made into Python: "render_props", "get_children", "_generate_html_stub"
+    #[pyo3(get)]
+    pub python_calls: HashMap<String, usize>,
+    /// Nodes memo mode took over from the previous tree without building
+    #[pyo3(get)]
+    pub reused_nodes: usize,
+    /// Subtrees kept without prop diffs or patches (memoized or with an
+    /// unchanged hash); their nodes are still visited for details and hooks
+    #[pyo3(get)]
+    pub kept_subtrees: usize,
+}
+
+impl PyReconcileStats {
+    pub fn new(core: ReconcileStats, py_stats: PyStats) -> Self {
+        let ms = |d: std::time::Duration| d.as_secs_f64() * 1000.0;
+        PyReconcileStats {
+            old_node_count: core.old_node_count,
+            new_node_count: core.new_node_count,
+            patch_counts: core.patch_counts.into_iter()
+                .map(|(action, n)| (action.to_string(), n))
+                .collect(),
+            lis_lengths: core.lis_lengths,
+            build_tree_ms: ms(py_stats.build_tree_time),
+            diff_ms: ms(core.diff_duration),
+            lifecycle_ms: ms(core.lifecycle_duration),
+            html_stub_ms: ms(py_stats.html_stub_time),
+            to_python_ms: ms(py_stats.to_python_time),
+            python_calls: HashMap::from([
+                ("render_props".to_string(), py_stats.render_props_calls),
+                ("get_children".to_string(), py_stats.get_children_calls),
+                ("_generate_html_stub".to_string(), py_stats.generate_html_stub_calls),
+            ]),
+            reused_nodes: py_stats.reused_nodes,
+            kept_subtrees: core.kept_subtrees,
+        }
+    }
+}
+
+#[pymethods]
+impl PyReconcileStats {
+    fn to_dict<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyDict>> {
+        let dict = PyDict::new(py);
+        dict.set_item("old_node_count", self.old_node_count)?;
+        dict.set_item("new_node_count", self.new_node_count)?;
+        dict.set_item("patch_counts", self.patch_counts.clone())?;
+        dict.set_item("lis_lengths", self.lis_lengths.clone())?;
+        dict.set_item("build_tree_ms", self.build_tree_ms)?;
+        dict.set_item("diff_ms", self.diff_ms)?;
+        dict.set_item("lifecycle_ms", self.lifecycle_ms)?;
+        dict.set_item("html_stub_ms", self.html_stub_ms)?;
+        dict.set_item("to_python_ms", self.to_python_ms)?;
+        dict.set_item("python_calls", self.python_calls.clone())?;
+        dict.set_item("reused_nodes", self.reused_nodes)?;
+        dict.set_item("kept_subtrees", self.kept_subtrees)?;
+        Ok(dict)
+    }
+
+    fn __repr__(&self) -> String {
+        format!(
+            "ReconcileStats(old_node_count={}, new_node_count={}, build_tree_ms={:.3}, diff_ms={:.3}, lifecycle_ms={:.3}, html_stub_ms={:.3}, to_python_ms={:.3})",
+            self.old_node_count, self.new_node_count, self.build_tree_ms, self.diff_ms, self.lifecycle_ms, self.html_stub_ms, self.to_python_ms
+        )
+    }
+}
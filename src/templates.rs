@@ -0,0 +1,102 @@
+//! Declarative stub templates registered from Python with
+//! `Reconciler.register_widget_template`:
+//!
+//! ```python
+//! {
+//!     "tag": "input",
+//!     "void": True,
+//!     "static_attributes": {"type": "checkbox"},
+//!     "attributes": {"name": "field_name"},                # attr <- prop
+//!     "styles": {"width": {"prop": "width", "unit": "px"},  # css <- prop
+//!                "color": "color"},
+//!     "text": "label",                                      # inner text <- prop
+//! }
+//! ```
+//!
+//! Units are appended to numeric prop values only, as the built-in SizedBox
+//! branch does. Bindings to missing or null props are skipped.
+use crate::errors::ReconcilerError;
+use reconciler_core::markup::{is_event_handler_attribute, is_valid_attribute_name, is_valid_tag_name};
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum StyleBinding {
+    Prop(String),
+    WithUnit { prop: String, unit: String },
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WidgetTemplate {
+    pub tag: String,
+    #[serde(default)]
+    pub void: bool,
+    #[serde(default)]
+    pub static_attributes: BTreeMap<String, String>,
+    #[serde(default)]
+    pub attributes: BTreeMap<String, String>,
+    #[serde(default)]
+    pub styles: BTreeMap<String, StyleBinding>,
+    #[serde(default)]
+    pub text: Option<String>,
+}
+
+/// Prop value as it appears in markup; None for missing/null
+fn prop_text(props: &HashMap<String, serde_json::Value>, prop: &str) -> Option<String> {
+    match props.get(prop)? {
+        serde_json::Value::Null => None,
+        serde_json::Value::String(s) => Some(s.clone()),
+        other => Some(other.to_string()),
+    }
+}
+
+impl WidgetTemplate {
+    /// Parse and validate a template given as JSON
+    pub fn from_json(value: serde_json::Value) -> Result<Self, ReconcilerError> {
+        let template: WidgetTemplate = serde_json::from_value(value)
+            .map_err(|e| ReconcilerError::TemplateError { details: e.to_string() })?;
+        if !is_valid_tag_name(&template.tag) {
+            return Err(ReconcilerError::TemplateError {
+                details: format!("invalid HTML tag name '{}'", template.tag),
+            });
+        }
+        let mut attribute_names = template.static_attributes.keys().chain(template.attributes.keys());
+        if let Some(bad) = attribute_names.find(|n| !is_valid_attribute_name(n) || is_event_handler_attribute(n)) {
+            return Err(ReconcilerError::TemplateError {
+                details: format!("invalid HTML attribute name '{}'", bad),
+            });
+        }
+        Ok(WidgetTemplate { tag: template.tag.to_ascii_lowercase(), ..template })
+    }
+
+    /// (attribute, value) pairs: static ones first, then prop bindings with
+    /// the raw prop value so booleans render as boolean attributes
+    pub fn attributes(&self, props: &HashMap<String, serde_json::Value>) -> Vec<(String, serde_json::Value)> {
+        let fixed = self.static_attributes.iter().map(|(k, v)| (k.clone(), v.clone().into()));
+        let bound = self.attributes.iter()
+            .filter_map(|(attr, prop)| Some((attr.clone(), props.get(prop)?.clone())));
+        fixed.chain(bound).collect()
+    }
+
+    /// (property, value) declarations for the inline style
+    pub fn styles(&self, props: &HashMap<String, serde_json::Value>) -> Vec<(String, String)> {
+        self.styles.iter()
+            .filter_map(|(property, binding)| {
+                let value = match binding {
+                    StyleBinding::Prop(prop) => prop_text(props, prop)?,
+                    StyleBinding::WithUnit { prop, unit } => match props.get(prop)? {
+                        serde_json::Value::Number(n) => format!("{}{}", n, unit),
+                        _ => prop_text(props, prop)?,
+                    },
+                };
+                Some((property.clone(), value))
+            })
+            .collect()
+    }
+
+    pub fn text(&self, props: &HashMap<String, serde_json::Value>) -> Option<String> {
+        prop_text(props, self.text.as_deref()?)
+    }
+}
@@ -0,0 +1,361 @@
+//! Builds the new `NodeMap` by walking a Python widget tree
+use crate::converters::py_dict_to_rust_map;
+use crate::errors::ReconcilerError;
+use crate::html_generator::{StubConfig, generate_html_stub};
+use crate::types::{NodeMap, PyObjectWrapper, PyStats, RustNodeData};
+use log::{Level, debug, log_enabled, trace, warn};
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+use pyo3::types::{PyDict, PyList};
+use reconciler_core::{IdGenerator, disambiguated_key, key_path};
+use reconciler_core::html::{StartTag, scan_start_tags};
+use std::collections::{HashMap, VecDeque};
+use std::time::Instant;
+
+/// Options that change how keys are resolved while building
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BuildOptions {
+    /// Rename duplicate keys to `<parent key>/<key>#<sibling index>` instead
+    /// of failing with `DuplicateKey`
+    pub auto_disambiguate_keys: bool,
+    /// Reuse old subtrees whose widgets report no change (see
+    /// [`TreeBuilder::reuse_subtree`])
+    pub memoize: bool,
+}
+
+/// A node just added by [`TreeBuilder::build_single`]
+pub struct BuiltNode<'py> {
+    pub key: String,
+    /// html_id the children attach to (this node's, or the nearest
+    /// renderable ancestor's for transparent widgets)
+    pub child_parent_id: String,
+    pub children: Bound<'py, PyList>,
+}
+
+pub struct TreeBuilder<'a, 'py> {
+    py: Python<'py>,
+    /// Previously rendered tree; matching nodes keep their html_id
+    old_map: &'a NodeMap,
+    ids: &'a IdGenerator,
+    config: &'a StubConfig,
+    options: BuildOptions,
+    map: NodeMap,
+    stats: &'a mut PyStats,
+    /// Server-rendered elements still to be claimed, in document order
+    adopt: Option<VecDeque<StartTag>>,
+}
+
+impl<'a, 'py> TreeBuilder<'a, 'py> {
+    pub fn new(
+        py: Python<'py>,
+        old_map: &'a NodeMap,
+        ids: &'a IdGenerator,
+        config: &'a StubConfig,
+        options: BuildOptions,
+        stats: &'a mut PyStats,
+    ) -> Self {
+        TreeBuilder { py, old_map, ids, config, options, map: HashMap::new(), stats, adopt: None }
+    }
+
+    /// Hydrate: renderable widgets take the ids of `elements`, one each in
+    /// tree order, instead of minting new ones
+    pub fn adopting(mut self, elements: Vec<StartTag>) -> Self {
+        for element in &elements {
+            if let Some(id) = element.attr("id") {
+                self.ids.observe(id);
+            }
+        }
+        self.adopt = Some(elements.into());
+        self
+    }
+
+    /// Build the new tree map for an optional root widget
+    pub fn build(mut self, root: Option<&Bound<'py, PyAny>>, parent_html_id: &str) -> PyResult<NodeMap> {
+        let started = Instant::now();
+        if let Some(root) = root {
+            self.build_node(root, parent_html_id, None, 0)?;
+            reconciler_core::compute_subtree_hashes(&mut self.map);
+            debug!("Built new_map with {} entries.", self.map.len());
+            if let Some(left) = self.adopt.as_ref().filter(|left| !left.is_empty()) {
+                warn!("hydrate: {} server-rendered elements have no matching widget, first is id '{}'",
+                    left.len(), left[0].attr("id").unwrap_or(""));
+            }
+            if log_enabled!(Level::Trace) {
+                for k in self.map.keys() {
+                    trace!("new_map key => {}", k);
+                }
+            }
+        }
+        self.stats.build_tree_time += started.elapsed();
+        Ok(self.map)
+    }
+
+    /// Continue building into a partially built map (used by streaming)
+    pub fn with_map(mut self, map: NodeMap) -> Self {
+        self.map = map;
+        self
+    }
+
+    pub fn into_map(self) -> NodeMap {
+        self.map
+    }
+
+    fn build_node(
+        &mut self,
+        widget: &Bound<'py, PyAny>,
+        parent_html_id: &str,
+        parent_key: Option<&str>,
+        sibling_index: usize,
+    ) -> PyResult<()> {
+        if self.options.memoize && self.adopt.is_none() && self.reuse_subtree(widget, parent_html_id, parent_key)? {
+            return Ok(());
+        }
+        let built = self.build_single(widget, parent_html_id, parent_key, sibling_index)?;
+        for (index, child) in built.children.iter().enumerate() {
+            self.build_node(&child, &built.child_parent_id, Some(&built.key), index)?;
+        }
+        Ok(())
+    }
+
+    /// Add one widget to the map without descending into its children
+    pub fn build_single(
+        &mut self,
+        widget: &Bound<'py, PyAny>,
+        parent_html_id: &str,
+        parent_key: Option<&str>,
+        sibling_index: usize,
+    ) -> PyResult<BuiltNode<'py>> {
+        let py = self.py;
+        // FIX: get_type() returns Bound<PyType>, call .name() on it
+        let widget_type = widget.get_type().name()?.to_string();
+        let mut widget_key = resolve_widget_key(widget)?;
+        if self.map.contains_key(&widget_key) {
+            widget_key = self.resolve_duplicate_key(widget_key, &widget_type, parent_key, sibling_index)?;
+        }
+        trace!("build_new_tree_map: widget key resolved = {}", widget_key);
+
+        let adopted = match self.adopt.as_mut() {
+            Some(left) if reconciler_core::is_renderable_type(&widget_type) => {
+                let element = left.pop_front();
+                if element.is_none() {
+                    warn!("hydrate: no server-rendered element left for {} '{}'", widget_type, widget_key);
+                }
+                element
+            }
+            _ => None,
+        };
+        let html_id = if let Some(id) = adopted.as_ref().and_then(|e| e.attr("id")) {
+            id.to_string()
+        } else {
+            // A node with the same key and type is the same DOM element: keep
+            // its id so UPDATE/MOVE patches target something that exists
+            match self.old_map.get(&widget_key) {
+                Some(old) if old.widget_type == widget_type => old.html_id.clone(),
+                _ => self.ids.next_id(|| {
+                    let mut path = key_path(&self.map, parent_key);
+                    path.push(widget_key.clone());
+                    path
+                }),
+            }
+        };
+
+        // Obtain props by calling widget.render_props() on the Python side
+        let props_any = widget.getattr("render_props")?.call0()?;
+        self.stats.render_props_calls += 1;
+        // FIX: Use cast instead of cast_as (cast_as doesn't exist)
+        let props_dict = props_any.cast::<PyDict>().map_err(|e| {
+            PyValueError::new_err(format!("render_props did not return a dict: {}", e))
+        })?;
+        let props = py_dict_to_rust_map(py, props_dict)?;
+
+        let children_any = widget.getattr("get_children")?.call0()?;
+        self.stats.get_children_calls += 1;
+        // FIX: Use cast instead of cast_as (cast_as doesn't exist)
+        let children_list = children_any.cast::<PyList>().map_err(|e| {
+            PyValueError::new_err(format!("get_children did not return a list: {}", e))
+        })?;
+        let mut children_keys: Vec<String> = Vec::new();
+        for child in children_list.iter() {
+            let id = resolve_widget_key(&child)?;
+            children_keys.push(id);
+        }
+
+        let widget_instance_py: Py<PyAny> = widget.clone().unbind();
+
+        // Generate HTML stub for the widget using Rust generator to keep parity
+        let generated_html = match generate_html_stub(py, widget_instance_py.clone_ref(py), &html_id, &props, self.config, Some(&mut *self.stats)) {
+            Ok(s) => s,
+            Err(e) => {
+                // Fallback to empty string on error but log for debugging
+                warn!("html generation failed for {}: {}", widget_key, e);
+                String::new()
+            }
+        };
+
+        if let Some(element) = adopted
+            && let Some(stub_tag) = scan_start_tags(&generated_html).first()
+            && stub_tag.name != element.name
+        {
+            warn!("hydrate: {} '{}' renders <{}> but the server element '{}' is <{}>",
+                widget_type, widget_key, stub_tag.name, html_id, element.name);
+        }
+
+        let node = RustNodeData {
+            html_id: html_id.clone(),
+            html: generated_html,
+            widget_type: widget_type.clone(),
+            key: widget_key.clone(),
+            widget_instance: Some(PyObjectWrapper(widget_instance_py)),
+            props,
+            parent_html_id: parent_html_id.to_string(),
+            parent_key: parent_key.map(String::from),
+            children_keys,
+            memo_key: if self.options.memoize { memo_key(widget)? } else { None },
+            memoized: false,
+            subtree_hash: None,
+        };
+
+        self.map.insert(widget_key.clone(), node);
+
+        // EXACT Python parity: children of non-renderable widgets use the parent's
+        // html id (the nearest renderable ancestor). Children of renderable
+        // widgets attach to this widget's generated html_id.
+        let child_parent_id = if reconciler_core::is_renderable_type(&widget_type) { html_id } else { parent_html_id.to_string() };
+
+        Ok(BuiltNode { key: widget_key, child_parent_id, children: children_list.clone() })
+    }
+
+    /// Copy the old subtree of `widget` into the map without calling
+    /// `render_props`/`get_children` or generating stubs, when the widget
+    /// sits where it did before and either `widget.should_update(old_widget)`
+    /// is falsy or, lacking that method, its `memo_key` equals the old one.
+    /// Returns false when the widget has to be built.
+    fn reuse_subtree(
+        &mut self,
+        widget: &Bound<'py, PyAny>,
+        parent_html_id: &str,
+        parent_key: Option<&str>,
+    ) -> PyResult<bool> {
+        let widget_type = widget.get_type().name()?.to_string();
+        let widget_key = resolve_widget_key(widget)?;
+        let Some(old) = self.old_map.get(&widget_key) else {
+            return Ok(false);
+        };
+        if self.map.contains_key(&widget_key)
+            || old.widget_type != widget_type
+            || old.parent_html_id != parent_html_id
+            || old.parent_key.as_deref() != parent_key
+        {
+            return Ok(false);
+        }
+
+        let memo_key = memo_key(widget)?;
+        let unchanged = match widget.getattr_opt("should_update")? {
+            Some(should_update) => match old.widget_instance {
+                Some(ref old_widget) => !should_update.call1((old_widget.0.bind(self.py),))?.is_truthy()?,
+                None => false,
+            },
+            None => memo_key.is_some() && memo_key == old.memo_key,
+        };
+        if !unchanged {
+            return Ok(false);
+        }
+
+        let mut subtree = Vec::new();
+        let mut stack = vec![widget_key.as_str()];
+        while let Some(key) = stack.pop() {
+            match self.old_map.get(key) {
+                Some(node) if !self.map.contains_key(key) => {
+                    subtree.push(node);
+                    stack.extend(node.children_keys.iter().map(String::as_str));
+                }
+                // Incomplete old map or a key already taken in the new one
+                _ => return Ok(false),
+            }
+        }
+
+        trace!("memo: reusing {} nodes under '{}'", subtree.len(), widget_key);
+        self.stats.reused_nodes += subtree.len();
+        for node in subtree {
+            let mut node = node.clone();
+            if node.key == widget_key {
+                node.widget_instance = Some(PyObjectWrapper(widget.clone().unbind()));
+                node.memo_key = memo_key.clone();
+                node.memoized = true;
+            }
+            self.map.insert(node.key.clone(), node);
+        }
+        Ok(true)
+    }
+
+    /// Handle a key that is already in the new map: either rename it by its
+    /// position under the parent (patching the parent's `children_keys`) or
+    /// report both colliding widgets.
+    fn resolve_duplicate_key(
+        &mut self,
+        key: String,
+        widget_type: &str,
+        parent_key: Option<&str>,
+        sibling_index: usize,
+    ) -> Result<String, ReconcilerError> {
+        if self.options.auto_disambiguate_keys
+            && let Some(parent_key) = parent_key
+        {
+            let candidate = disambiguated_key(parent_key, &key, sibling_index);
+            if !self.map.contains_key(&candidate) {
+                warn!("Duplicate key '{}' ({}) renamed to '{}'", key, widget_type, candidate);
+                if let Some(slot) = self.map.get_mut(parent_key)
+                    .and_then(|parent| parent.children_keys.get_mut(sibling_index))
+                {
+                    *slot = candidate.clone();
+                }
+                return Ok(candidate);
+            }
+        }
+
+        let first_type = self.map.get(&key).map(|n| n.widget_type.clone()).unwrap_or_default();
+        let mut second_path = key_path(&self.map, parent_key);
+        second_path.push(key.clone());
+        Err(ReconcilerError::DuplicateKey {
+            first_path: key_path(&self.map, Some(&key)).join(" > "),
+            second_path: second_path.join(" > "),
+            key,
+            first_type,
+            second_type: widget_type.to_string(),
+        })
+    }
+}
+
+/// Resolve a widget's `get_unique_id()` to a String. The Python side may
+/// return either a plain `str` or a `Key` object: try extracting a String
+/// directly, then a `__str_key__` method, then fall back to Python's str().
+/// This mirrors the logic used when reading the previous_map so both sides
+/// agree on key stringification.
+fn resolve_widget_key(widget: &Bound<'_, PyAny>) -> Result<String, ReconcilerError> {
+    let key_obj = widget.getattr("get_unique_id")?.call0()?;
+    if let Ok(s) = key_obj.extract::<String>() {
+        return Ok(s);
+    }
+    if let Ok(m) = key_obj.getattr("__str_key__") {
+        return m.call0()?.extract::<String>().map_err(|e| ReconcilerError::KeyError {
+            details: format!("get_unique_id().__str_key__ did not return a string: {}", e),
+        });
+    }
+    key_obj.str()?.to_str().map(|s| s.to_string()).map_err(|e| ReconcilerError::KeyError {
+        details: format!("Cannot convert widget key to string: {}", e),
+    })
+}
+
+/// `widget.memo_key` (called if it is a method) as a string, if set
+fn memo_key(widget: &Bound<'_, PyAny>) -> PyResult<Option<String>> {
+    let Some(mut value) = widget.getattr_opt("memo_key")? else {
+        return Ok(None);
+    };
+    if value.is_callable() {
+        value = value.call0()?;
+    }
+    if value.is_none() {
+        return Ok(None);
+    }
+    Ok(Some(value.str()?.to_string()))
+}